@@ -25,6 +25,28 @@
 //! humility: adt7420            TEMPS_BYSECOND                 0x20008000 14408
 //! ```
 //!
+//! To list only those variables whose module or name contains a particular
+//! substring, specify it along with `-l`; to instead treat the pattern as a
+//! regular expression, use `--regex`:
+//!
+//! ```console
+//! $ humility readvar -l adt7420
+//! humility: MODULE             VARIABLE                       ADDR       SIZE
+//! humility: adt7420            TEMPS_BYMINUTE                 0x2000b848 17288
+//! humility: adt7420            TEMPS_BYSECOND                 0x20008000 14408
+//! $ humility readvar -l --regex '^IRQ_TABLE_'
+//! humility: MODULE             VARIABLE                       ADDR       SIZE
+//! humility: kernel             IRQ_TABLE_BASE                 0x20000010 4
+//! humility: kernel             IRQ_TABLE_SIZE                 0x20000014 4
+//! ```
+//!
+//! By default, variables are listed by module and then by name; to sort by
+//! a different field, use `--sort` with `addr`, `size` or `name` (e.g.,
+//! `--sort size` will display the largest variables last).
+//!
+//! With `--json`, the listing is instead printed as an array of objects with
+//! `module`, `name`, `addr` and `size` members.
+//!
 //! To read a variable, specify it:
 //!
 //! ```console
@@ -35,13 +57,359 @@
 //!     })
 //! ```
 //!
+//! A variable may also be specified by its address as displayed by `-l`
+//! (e.g., `humility readvar 0x2000b848` to read `TEMPS_BYMINUTE`).
+//!
+//! Multiple variables may be specified; the target will be halted only once
+//! while all of them are read, assuring that their values are consistent with
+//! one another.
+//!
+//! If the target is already halted (e.g., by a debugger), it will be left
+//! halted after variables have been read.
+//!
+//! If halting the target is undesirable (e.g., because it would perturb the
+//! timing of a peripheral), `--no-halt` will read variables while the target
+//! continues to run.  Note that in this case, a variable that is being
+//! modified as it is read may be displayed with an inconsistent (torn)
+//! value, and multiple variables are not assured to be consistent with one
+//! another.
+//!
+//! Reads made while the target is halted may be satisfied from a cache of
+//! target memory; to assure that variables are always read from the target
+//! (e.g., when polling a peripheral's shadow register with `--watch` while
+//! the target is halted), use `--volatile`.  This is implied for variables
+//! in device memory.
+//!
+//! To read only a member of a structure or an element of an array, follow the
+//! variable name with the path to the member, e.g.:
+//!
+//! ```console
+//! $ humility readvar TEMPS_BYMINUTE[5]
+//! $ humility readvar CONFIG.thresholds[2].critical
+//! ```
+//!
+//! Only the designated member will be read from the target.  (Bitfield
+//! members -- e.g., of packed structures defined in C -- are displayed with
+//! their values extracted from the bits that they occupy, but cannot be
+//! designated this way; read the containing structure instead.)
+//!
+//! To interpret memory at an arbitrary address as a structure (e.g., to
+//! examine data that has been relocated or that is referred to only by a
+//! pointer), specify the name of the structure, followed by `@` and the
+//! address; a path of members and/or array subscripts may follow, as with
+//! variables:
+//!
+//! ```console
+//! $ humility readvar Task@0x20000558.save.psp
+//! humility: attached via ST-Link
+//! Task@0x20000558.save.psp (0x20000578) = 0x200013b0
+//! ```
+//!
+//! Enums are displayed by the name of their active variant (as determined
+//! from the variant's discriminant), followed by the variant's contents, if
+//! any:
+//!
+//! ```console
+//! $ humility readvar TASK_STATE
+//! humility: attached via ST-Link
+//! TASK_STATE (0x20000d10) = Running {
+//!         since: 0x2a
+//!     }
+//! ```
+//!
+//! For large arrays, `--start` and `--count` can be used to display (and
+//! read) only a window of elements:
+//!
+//! ```console
+//! $ humility readvar TEMPS_BYSECOND --start 100 --count 2
+//! humility: attached via ST-Link
+//! TEMPS_BYSECOND[100] (0x20001390) = 0x0
+//! TEMPS_BYSECOND[101] (0x20001394) = 0x0
+//! ```
+//!
+//! If more than one task defines a variable of the same name, all of them
+//! will be read; to read only the variable defined by a particular task, use
+//! `--task` (e.g., `readvar --task jefe TASK_STATES`).  `--task` can also be
+//! used with `--list` to list only the variables of a particular task.
+//!
+//! To control how values are displayed, use `--format` with a
+//! comma-separated list of specifiers: `hex` (the default) or `dec` to
+//! display integers in hexadecimal or decimal; `bin` to display integers in
+//! binary; `cstr` to interpret byte arrays as C strings; `str` to display
+//! byte arrays as strings when they contain printable text (equivalent to
+//! `--string`); `oneline` to display structures and arrays on a single
+//! line; and `compact` to display each scalar within the value as a
+//! `path=value` pair, all on a single line (which is easier to grep):
+//!
+//! ```console
+//! $ humility readvar --format bin,oneline TEST_FLAGS
+//! humility: attached via ST-Link
+//! TEST_FLAGS (0x20000d08) = Flags { bits: 0b1001 }
+//! $ humility readvar --format compact TASK_STATE
+//! humility: attached via ST-Link
+//! TASK_STATE (0x20000d10) = Running since=0x2a
+//! ```
+//!
+//! By default, each level of a nested structure or array is indented by four
+//! spaces; to use a different width (e.g., 0 for a more compact display),
+//! use `--indent`.
+//!
+//! Arrays of structures (e.g., per-sensor configuration) can be verbose when
+//! each structure is displayed in full; to instead display a table with a
+//! row for each element and a column for each member, use `--table`:
+//!
+//! ```console
+//! $ humility readvar --table SENSOR_CONFIG
+//! humility: attached via ST-Link
+//!                  id  kind        threshold enabled
+//! SENSOR_CONFIG[0] 0x0 Temperature 0x50      true
+//! SENSOR_CONFIG[1] 0x1 Temperature 0x55      true
+//! SENSOR_CONFIG[2] 0x2 Fan         0x0       false
+//! ```
+//!
+//! For variables that hold fixed-point or otherwise scaled quantities (e.g.,
+//! sensor readings), `--scale` displays each numeric value (in decimal)
+//! multiplied by the specified factor, and `--unit` displays each numeric
+//! value followed by the specified unit.  Structures and arrays are displayed
+//! as usual, and only the display is affected -- not the value that is read
+//! (or written with `--output`):
+//!
+//! ```console
+//! $ humility readvar --scale 0.0625 --unit C --count 2 RAW_TEMPS
+//! humility: attached via ST-Link
+//! RAW_TEMPS[0] (0x2000b848) = 23.5 C
+//! RAW_TEMPS[1] (0x2000b84a) = 23.5625 C
+//! ```
+//!
+//! Large structures (e.g., configuration) in which most members are zero can
+//! be noisy; to omit members that are zero (or `None`, or null, or
+//! aggregates consisting entirely of such members), use `--brief`.  The
+//! number of members that were omitted is displayed in their place:
+//!
+//! ```console
+//! $ humility readvar --brief CONFIG
+//! humility: attached via ST-Link
+//! CONFIG (0x20000c00) = Config {
+//!     baud: 0x1c200,
+//!     ... (14 fields omitted)
+//! }
+//! ```
+//!
+//! To also display the type of each variable (e.g., to distinguish between
+//! like-named variables in different modules), use `--show-type`:
+//!
+//! ```console
+//! $ humility readvar --show-type CURRENT_TASK_PTR
+//! humility: attached via ST-Link
+//! CURRENT_TASK_PTR: Option<NonNull<kern::task::Task>> (0x20000018) = Some(NonNull<kern::task::Task> {
+//!         pointer: 0x20000558 (*const kern::task::Task)
+//!     })
+//! ```
+//!
+//! Type names (in `--show-type` and in the display of pointers and
+//! structures) include module paths, which can make for long lines; to
+//! display type names without module paths, use `--short-names`:
+//!
+//! ```console
+//! $ humility readvar --show-type --short-names CURRENT_TASK_PTR
+//! humility: attached via ST-Link
+//! CURRENT_TASK_PTR: Option<NonNull<Task>> (0x20000018) = Some(NonNull<Task> {
+//!         pointer: 0x20000558 (*const Task)
+//!     })
+//! ```
+//!
+//! Fixed-capacity vectors (e.g., `heapless::Vec`) consist of a length and a
+//! backing array, of which only the first `len` elements are live; by default,
+//! the entire backing array is displayed.  To display only the live elements
+//! of any structure that consists of a `len` and a `buffer` or `data` array,
+//! use `--follow-vec`:
+//!
+//! ```console
+//! $ humility readvar --follow-vec PENDING
+//! humility: attached via ST-Link
+//! PENDING (0x20000f40) = Vec<u8, 8> {
+//!         buffer: [
+//!             0x3,
+//!             0x7
+//!         ],
+//!         len: 0x2
+//!     }
+//! ```
+//!
+//! To dump the contents of a variable without interpreting them according to
+//! its type (e.g., when debugging a type mismatch), use `--raw`:
+//!
+//! ```console
+//! $ humility readvar --raw IRQ_TABLE_BASE
+//! humility: attached via ST-Link
+//! IRQ_TABLE_BASE (0x20000010):
+//!              \/  1  2  3  4  5  6  7  8  9  a  b  c  d  e  f
+//! 0x20000010 | 4c 9f 00 08                                     | L...
+//! ```
+//!
+//! To also read and display the value that a pointer variable points to,
+//! use `--deref`.  This will follow a raw pointer, or a pointer contained
+//! within (say) a `NonNull` or an `Option`; null pointers and pointers that
+//! do not fall within a known region are not followed:
+//!
+//! ```console
+//! $ humility readvar --deref IRQ_TABLE_BASE
+//! humility: attached via ST-Link
+//! IRQ_TABLE_BASE (0x20000010) = Some(NonNull<abi::Interrupt> {
+//!         pointer: 0x8009f4c (*const abi::Interrupt)
+//!     })
+//!     *IRQ_TABLE_BASE (0x08009f4c) = Interrupt {
+//!             irq: 0x26,
+//!             owner: InterruptOwner {
+//!                 task: 0x1,
+//!                 notification: 0x1
+//!             }
+//!         }
+//! ```
+//!
+//! On a multicore target, the value of a variable may differ from core to
+//! core; to select the core whose view of memory is read, use `--hart`
+//! (which defaults to 0; a hart beyond the number of cores on the target
+//! results in an error).
+//!
+//! If the archive's debug information is stale relative to the image running
+//! on the target (e.g., when debugging a locally built binary), variables may
+//! instead be looked up in a standalone ELF object with `--elf`; memory will
+//! still be read from the target, and the archive will still be used to
+//! determine the target's memory regions.
+//!
+//! To save the values of variables to a file (e.g., to share them with
+//! someone who has the same archive but not the target), use `--output`;
+//! the values can then be displayed later without a target with `--replay`:
+//!
+//! ```console
+//! $ humility readvar --output ticks.var TICKS
+//! humility: attached via ST-Link
+//! humility: captured 1 variable(s) to ticks.var
+//! kern::arch::arm_m::TICKS (0x20001120) = 0xa7dae
+//! $ humility -p archive readvar --replay ticks.var
+//! humility: attached to archive
+//! kern::arch::arm_m::TICKS (0x20001120) = 0xa7dae
+//! ```
+//!
+//! To compare two captures of the same variables (e.g., before and after
+//! some operation), use `--diff`, which displays each member whose value
+//! differs between the two:
+//!
+//! ```console
+//! $ humility -p archive readvar --diff before.var after.var
+//! humility: attached to archive
+//! TEST_STATE (0x20000d10):
+//!     TEST_STATE.count: 0x10 -> 0x13
+//!     TEST_STATE.mode: "Idle" -> "Busy"
+//! ```
+//!
+//! To get the value as JSON, use `--json`; this will print an object with
+//! `name`, `type`, `addr`, `size` and `value` members, in which structures are
+//! represented as objects, arrays as arrays, and enums as externally tagged
+//! values:
+//!
+//! ```console
+//! $ humility readvar --json CURRENT_TASK_PTR
+//! humility: attached via ST-Link
+//! {"addr":536870936,"name":"CURRENT_TASK_PTR","size":4,"type":"Option<NonNull<kern::task::Task>>","value":{"Some":{"pointer":{"addr":536872280,"type":"*const kern::task::Task"}}}}
+//! ```
+//!
+//! With `--json`, failures are also reported as JSON:  an object with an
+//! `error` member (the error message), a `variable` member (the variable
+//! that failed, if any), and a `kind` member that is `lookup` if the
+//! variable could not be found, `read` if it could not be read from the
+//! target, or `decode` if it could not be interpreted.  In this case, the
+//! error is not otherwise displayed, but the command still fails:
+//!
+//! ```console
+//! $ humility readvar --json NO_SUCH_VARIABLE
+//! humility: attached via ST-Link
+//! {"error":"variable 'NO_SUCH_VARIABLE' not found; use \"-l\" to list","kind":"lookup","variable":"NO_SUCH_VARIABLE"}
+//! ```
+//!
+//! Before a variable is read, it is checked against the target's memory
+//! regions; if any part of it lies outside of every known region (e.g.,
+//! because the debug information is stale), the command fails with an error
+//! naming the first unmapped address rather than reading garbage:
+//!
+//! ```console
+//! $ humility readvar STALE_BUF
+//! humility: attached via ST-Link
+//! humility readvar failed: STALE_BUF (0x20007f00, 512 bytes) spans unmapped memory at 0x20008000 (0x100 bytes not in any known region)
+//! ```
+//!
+//! To write a new value to a scalar variable in RAM, use `--write`.  The value
+//! is encoded according to the variable's type and then read back to confirm
+//! that the write took:
+//!
+//! ```console
+//! $ humility readvar TEST_COUNTER --write 0x10
+//! humility: attached via ST-Link
+//! TEST_COUNTER (0x20000d04) = 0x10
+//! ```
+//!
+//! Writing to aggregate types (structures, enums and arrays) is not
+//! supported.
+//!
+//! To check that a buffer still holds the pattern that it is filled with
+//! when logically empty (e.g., to verify that it was properly cleared), use
+//! `--assert-pattern`; each variable is reported as `clean` if its entire
+//! contents match the (repeating) pattern, or `dirty` at the first offset
+//! that doesn't, in which case the command fails.  The width of the pattern
+//! is given by its number of hex digits (e.g., `0xa5` for a byte or
+//! `0xbaddcafe` for a word):
+//!
+//! ```console
+//! $ humility readvar --assert-pattern 0xa5 RX_BUF TX_BUF
+//! humility: attached via ST-Link
+//! RX_BUF (0x20000e00): clean
+//! TX_BUF (0x20000f00): dirty at offset 0x24 (found 0x00, expected 0xa5)
+//! humility readvar failed: 1 of 2 variable(s) dirty
+//! ```
+//!
+//! To characterize a noisy value (e.g., a sensor reading), use `--samples`
+//! to read each variable the specified number of times (with a short gap
+//! between reads) and display the minimum, maximum and mean of each of its
+//! numeric values.  Members of aggregates are named by their path from the
+//! variable, as with `--diff`; a variable with no numeric values can't be
+//! sampled:
+//!
+//! ```console
+//! $ humility readvar --samples 16 TEST_SENSOR
+//! humility: attached via ST-Link
+//! TEST_SENSOR (0x20000d08), 16 samples:
+//!     TEST_SENSOR.raw: min 0x3e8, max 0x3f6, mean 1006.375
+//!     TEST_SENSOR.temp: min 24.5, max 25.25, mean 24.922
+//! ```
+//!
+//! To watch a variable change over time, use `--watch`; the variable will be
+//! read and printed (prefixed with a timestamp) every `--interval`
+//! milliseconds (1000 by default) until interrupted with Ctrl-C:
+//!
+//! ```console
+//! $ humility readvar --watch --interval 500 TEST_COUNTER
+//! humility: attached via ST-Link
+//! 2023-05-01 14:23:11.304 TEST_COUNTER (0x20000d04) = 0x10
+//! 2023-05-01 14:23:11.806 TEST_COUNTER (0x20000d04) = 0x13
+//! 2023-05-01 14:23:12.308 TEST_COUNTER (0x20000d04) = 0x17
+//! ```
+//!
 
-use anyhow::{bail, Result};
-use clap::{CommandFactory, Parser};
-use humility::core::Core;
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{CommandFactory, Parser, ValueEnum};
+use humility::core::{Core, CORE_HALT_TIMEOUT};
 use humility::hubris::*;
+use humility::reflect::{format_compact, Format, Json, Ptr, Value};
 use humility_cli::{ExecutionContext, Subcommand};
-use humility_cmd::{Archive, Attach, Command, CommandKind, Validate};
+use humility_cmd::{
+    Archive, Attach, Command, CommandKind, Dumper, ExitStatus, Validate,
+};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[clap(name = "readvar", about = env!("CARGO_PKG_DESCRIPTION"))]
@@ -55,120 +423,2047 @@ struct ReadvarArgs {
     #[clap(long)]
     as_c_string: bool,
 
+    /// display byte arrays that contain printable text as strings
+    #[clap(long)]
+    string: bool,
+
+    /// omit structure members that are zero (or otherwise default)
+    #[clap(long)]
+    brief: bool,
+
+    /// display numeric values multiplied by the specified factor
+    #[clap(long, value_name = "factor", conflicts_with = "json")]
+    scale: Option<f64>,
+
+    /// display numeric values followed by the specified unit
+    #[clap(long, value_name = "unit", conflicts_with = "json")]
+    unit: Option<String>,
+
     /// list variables
     #[clap(long, short)]
     list: bool,
 
+    /// comma-separated list of format specifiers for values
+    #[clap(long, short, value_enum, use_value_delimiter = true)]
+    format: Vec<FormatSpec>,
+
+    /// number of spaces by which to indent each level of nested values
+    #[clap(
+        long, value_name = "n", conflicts_with_all = &["raw", "json"],
+        parse(try_from_str = parse_int::parse)
+    )]
+    indent: Option<usize>,
+
     /// leave target halted
     #[clap(long)]
     leave_halted: bool,
 
-    #[clap(conflicts_with = "list")]
-    variable: Option<String>,
+    /// read variables without halting the target (values may be torn)
+    #[clap(long, conflicts_with_all = &["leave-halted", "write"])]
+    no_halt: bool,
+
+    /// always read variables from the target rather than from any cache of
+    /// target memory (implied for variables in device memory)
+    #[clap(long)]
+    volatile: bool,
+
+    /// generate JSON output
+    #[clap(long, short, conflicts_with = "watch")]
+    json: bool,
+
+    /// write the specified value to the variable
+    #[clap(long, short, value_name = "value", conflicts_with = "list")]
+    write: Option<String>,
+
+    /// repeatedly read the variable until interrupted
+    #[clap(long, conflicts_with_all = &["list", "leave-halted"])]
+    watch: bool,
+
+    /// interval between reads when watching, in milliseconds
+    #[clap(
+        long, short, value_name = "ms", default_value_t = 1000,
+        requires = "watch", parse(try_from_str = parse_int::parse)
+    )]
+    interval: u64,
+
+    /// with --list, treat the pattern as a regular expression
+    #[clap(long, requires = "list")]
+    regex: bool,
+
+    /// with --list, sort variables by the specified field (ascending)
+    #[clap(long, value_enum, requires = "list")]
+    sort: Option<ListOrder>,
+
+    /// dump the raw contents of each variable rather than interpreting them
+    #[clap(
+        long,
+        conflicts_with_all = &[
+            "decimal", "as-c-string", "string", "brief", "scale", "unit",
+            "format", "json"
+        ]
+    )]
+    raw: bool,
+
+    /// if a variable is a pointer, also read and display its target
+    #[clap(long, conflicts_with = "list")]
+    deref: bool,
+
+    /// on a multicore target, the core whose view of memory should be read
+    #[clap(long, value_name = "n", conflicts_with = "list")]
+    hart: Option<usize>,
+
+    /// look up variables in the specified ELF object rather than the archive
+    #[clap(long, value_name = "path")]
+    elf: Option<String>,
+
+    /// also write the values of the variables to the specified file
+    #[clap(
+        long, value_name = "path",
+        conflicts_with_all = &["list", "watch", "replay"]
+    )]
+    output: Option<String>,
+
+    /// display variables from a file written with --output rather than
+    /// reading them from the target
+    #[clap(
+        long, value_name = "path",
+        conflicts_with_all = &["list", "watch", "write", "deref"]
+    )]
+    replay: Option<String>,
+
+    /// display the differences between two files written with --output
+    #[clap(
+        long, number_of_values = 2, value_names = &["a", "b"],
+        conflicts_with_all = &[
+            "list", "watch", "write", "deref", "replay", "output", "raw",
+            "json"
+        ]
+    )]
+    diff: Vec<String>,
+
+    /// check that each variable consists entirely of the specified
+    /// (repeating) pattern, e.g. 0xa5 or 0xbaddcafe
+    #[clap(
+        long, value_name = "pattern",
+        conflicts_with_all = &[
+            "list", "watch", "write", "deref", "replay", "diff", "json", "raw"
+        ]
+    )]
+    assert_pattern: Option<String>,
+
+    /// read each variable the specified number of times, displaying the
+    /// minimum, maximum and mean of each of its numeric values
+    #[clap(
+        long, value_name = "n",
+        conflicts_with_all = &[
+            "list", "watch", "write", "deref", "replay", "diff", "output",
+            "assert-pattern", "json", "raw"
+        ],
+        parse(try_from_str = parse_int::parse)
+    )]
+    samples: Option<usize>,
+
+    /// with an array variable, the index of the first element to display
+    #[clap(
+        long, value_name = "index",
+        conflicts_with_all = &["list", "write", "replay"],
+        parse(try_from_str = parse_int::parse)
+    )]
+    start: Option<usize>,
+
+    /// with an array variable, the number of elements to display
+    #[clap(
+        long, value_name = "n",
+        conflicts_with_all = &["list", "write", "replay"],
+        parse(try_from_str = parse_int::parse)
+    )]
+    count: Option<usize>,
+
+    /// only consider variables defined by the specified task
+    #[clap(long, value_name = "task")]
+    task: Option<String>,
+
+    /// display the type of each variable along with its value
+    #[clap(long, short = 't', conflicts_with = "list")]
+    show_type: bool,
+
+    /// display structures (or arrays of structures) as a table, with a row
+    /// for each structure and a column for each member
+    #[clap(
+        long,
+        conflicts_with_all = &[
+            "list", "watch", "write", "deref", "replay", "diff", "json", "raw",
+            "assert-pattern", "samples"
+        ]
+    )]
+    table: bool,
+
+    /// display type names without their module paths
+    #[clap(long, conflicts_with_all = &["list", "raw", "json"])]
+    short_names: bool,
+
+    /// display only the live elements of fixed-capacity vectors (that is,
+    /// structures with a `len` and a `buffer` or `data` array, as with
+    /// `heapless::Vec`)
+    #[clap(long, conflicts_with_all = &["list", "raw"])]
+    follow_vec: bool,
+
+    /// variables to read (or, with --list, a pattern to filter variables)
+    variables: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "kebab-case")]
+enum FormatSpec {
+    /// Display integers in hexadecimal (the default).
+    Hex,
+    /// Display integers in decimal.
+    Dec,
+    /// Display integers in binary.
+    Bin,
+    /// Interpret byte arrays as C strings.
+    Cstr,
+    /// Display byte arrays that contain printable text as strings.
+    Str,
+    /// Display aggregates on a single line.
+    Oneline,
+    /// Display each scalar as a path=value pair, all on a single line.
+    Compact,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "kebab-case")]
+enum ListOrder {
+    /// Sort by address.
+    Addr,
+    /// Sort by size.
+    Size,
+    /// Sort by qualified name.
+    Name,
+}
+
+///
+/// Encodes a value expressed as a string into the representation of the
+/// specified scalar type.
+///
+fn readvar_encode(
+    hubris: &HubrisArchive,
+    goff: HubrisGoff,
+    value: &str,
+) -> Result<Vec<u8>> {
+    let err = |err: &dyn std::fmt::Display| {
+        anyhow!("illegal value \"{}\": {}", value, err)
+    };
+
+    let base = match hubris.lookup_type(goff)? {
+        HubrisType::Base(base) => *base,
+        HubrisType::Ptr(_) => {
+            HubrisBasetype { encoding: HubrisEncoding::Unsigned, size: 4 }
+        }
+        t => {
+            bail!("cannot write to {}: only scalars are supported", t);
+        }
+    };
+
+    let bits = base.size * 8;
+
+    //
+    // We encode little-endian, and reverse the bytes for a big-endian target.
+    //
+    let mut buf = match (base.encoding, base.size) {
+        (HubrisEncoding::Unsigned, 1 | 2 | 4 | 8) => {
+            let v: u64 = parse_int::parse(value).map_err(|e| err(&e))?;
+
+            if bits < 64 && v >> bits != 0 {
+                bail!("value {} exceeds maximum for u{}", value, bits);
+            }
+
+            v.to_le_bytes()[..base.size].to_vec()
+        }
+        (HubrisEncoding::Signed, 1 | 2 | 4 | 8) => {
+            let v: i64 = parse_int::parse(value).map_err(|e| err(&e))?;
+
+            if bits < 64 && (v < -(1 << (bits - 1)) || v >= 1 << (bits - 1)) {
+                bail!("value {} out of range for i{}", value, bits);
+            }
+
+            v.to_le_bytes()[..base.size].to_vec()
+        }
+        (HubrisEncoding::Float, 4) => {
+            let v: f32 = value.parse().map_err(|e| err(&e))?;
+            v.to_le_bytes().to_vec()
+        }
+        (HubrisEncoding::Float, 8) => {
+            let v: f64 = value.parse().map_err(|e| err(&e))?;
+            v.to_le_bytes().to_vec()
+        }
+        (HubrisEncoding::Bool, 1) => {
+            let v: bool = value.parse().map_err(|e| err(&e))?;
+            vec![v as u8]
+        }
+        (_, _) => {
+            bail!("encoding of {:?} not yet supported", base);
+        }
+    };
+
+    if hubris.endianness() == HubrisEndianness::Big {
+        buf.reverse();
+    }
+
+    Ok(buf)
 }
 
-fn readvar_dump(
+fn readvar_write(
     hubris: &HubrisArchive,
     core: &mut dyn Core,
+    regions: &BTreeMap<u32, HubrisRegion>,
     variable: &HubrisVariable,
     name: &str,
+    value: &str,
     subargs: &ReadvarArgs,
 ) -> Result<()> {
-    let mut buf: Vec<u8> = vec![];
-    buf.resize_with(variable.size, Default::default);
-
-    core.halt()?;
-    core.read_8(variable.addr, buf.as_mut_slice())?;
+    let buf = readvar_encode(hubris, variable.goff, value)?;
 
-    if !subargs.leave_halted {
-        core.run()?;
+    if buf.len() != variable.size {
+        bail!(
+            "size of {} ({}) doesn't match size of its type ({})",
+            name,
+            variable.size,
+            buf.len()
+        );
     }
 
-    let hex = !subargs.decimal;
-
-    let fmt = HubrisPrintFormat {
-        newline: true,
-        hex,
-        interpret_as_c_string: subargs.as_c_string,
-        ..HubrisPrintFormat::default()
-    };
-    let dumped = hubris.printfmt(&buf, variable.goff, fmt)?;
+    //
+    // Make sure that we're writing to memory that is actually writable (and
+    // in particular, not to flash).
+    //
+    let end = variable.addr + variable.size as u32;
 
-    println!("{} (0x{:08x}) = {}", name, variable.addr, dumped);
+    match regions.range(..=variable.addr).next_back() {
+        Some((_, region))
+            if end <= region.base + region.size
+                && region.kind() == HubrisRegionKind::Ram => {}
+        _ => {
+            bail!("{} (0x{:08x}) is not in writable RAM", name, variable.addr);
+        }
+    }
 
-    Ok(())
+    readvar_store(core, variable, name, &buf, subargs)
 }
 
-fn readvar(context: &mut ExecutionContext) -> Result<()> {
-    let core = &mut **context.core.as_mut().unwrap();
-    let Subcommand::Other(subargs) = context.cli.cmd.as_ref().unwrap();
-    let hubris = context.archive.as_ref().unwrap();
+///
+/// Writes the specified (encoded) value to a variable and reads it back,
+/// halting the target once for both.  As with reading, a target that was
+/// already halted is left halted.
+///
+fn readvar_store(
+    core: &mut dyn Core,
+    variable: &HubrisVariable,
+    name: &str,
+    buf: &[u8],
+    subargs: &ReadvarArgs,
+) -> Result<()> {
+    let halted = core.is_halted()?;
 
-    let subargs = ReadvarArgs::try_parse_from(subargs)?;
+    if !halted {
+        core.halt_timeout(CORE_HALT_TIMEOUT)?;
+    }
 
-    if subargs.list {
-        println!("{:18} {:<42} {:<10} SIZE", "MODULE", "VARIABLE", "ADDR");
+    let mut readback = vec![0u8; variable.size];
 
-        let mut all: Vec<_> = hubris
-            .qualified_variables()
-            .map(|(n, v)| (HubrisTask::from(v.goff), n, v))
-            .collect::<_>();
+    let rval = core
+        .write_8(variable.addr, buf)
+        .and_then(|_| core.read_8(variable.addr, &mut readback));
 
-        all.sort();
+    if !subargs.leave_halted && !halted {
+        core.run()?;
+    }
 
-        for (task, name, v) in &all {
-            let task = &hubris.lookup_module(*task)?.name;
-            println!("{:18} {:<42} 0x{:08x} {:<}", task, name, v.addr, v.size);
-        }
+    rval?;
 
-        return Ok(());
+    if readback != buf {
+        bail!(
+            "wrote {:x?} to {} (0x{:08x}), but read back {:x?}",
+            buf,
+            name,
+            variable.addr,
+            readback
+        );
     }
 
-    fn match_exact(n: &str, v: &String) -> bool {
-        n == v
+    Ok(())
+}
+
+///
+/// Reads the specified variables (and, if `--deref` has been specified, the
+/// values that they point to).  The target is halted only once, so the
+/// values are all captured at a consistent point -- unless `--no-halt` has
+/// been specified, in which case the target is read while running.  If
+/// `volatile` is set, the variables are read from the target even if the
+/// core has cached them.
+///
+fn readvar_read(
+    hubris: &HubrisArchive,
+    core: &mut dyn Core,
+    regions: Option<&BTreeMap<u32, HubrisRegion>>,
+    variables: &[(String, HubrisVariable)],
+    volatile: bool,
+    subargs: &ReadvarArgs,
+) -> Result<Vec<(Vec<u8>, Option<Pointee>)>> {
+    if subargs.no_halt {
+        return readvar_read_halted(hubris, core, regions, variables);
     }
 
-    fn match_suffix(n: &str, v: &String) -> bool {
-        let mut suffix = "::".to_string();
-        suffix.push_str(v);
+    //
+    // If the core was already halted (e.g., by a debugger), we leave it
+    // that way rather than surprising the user by resuming it -- and we
+    // neither halt it nor run it, lest we unbalance its halt count.
+    //
+    let halted = core.is_halted()?;
 
-        n == v || n.ends_with(&suffix)
+    if !halted {
+        core.halt_timeout(CORE_HALT_TIMEOUT)?;
     }
 
-    if let Some(variable) = &subargs.variable {
-        let m =
-            if variable.contains("::") { match_exact } else { match_suffix };
+    //
+    // The core is shared with any subsequent command (e.g., in the REPL), so
+    // we restore its cache setting once we have read.
+    //
+    let cache = core.read_cache();
 
-        let mut found = false;
-        for (n, v) in
-            hubris.qualified_variables().filter(|&(n, _)| m(n, variable))
-        {
-            readvar_dump(hubris, core, v, n, &subargs)?;
-            found = true;
-        }
-        if !found {
-            bail!("variable '{variable}' not found; use \"-l\" to list");
-        }
+    if volatile {
+        core.set_read_cache(false);
+        core.invalidate_cache();
     } else {
-        bail!("expected variable (use \"-l\" to list)");
+        core.set_read_cache(true);
     }
 
-    if subargs.leave_halted {
-        humility::msg!("leaving target halted");
+    let rval = readvar_read_halted(hubris, core, regions, variables);
+    core.set_read_cache(cache);
+
+    if !subargs.leave_halted && !halted {
+        core.run()?;
     }
 
-    Ok(())
+    rval
 }
 
-pub fn init() -> Command {
-    Command {
-        app: ReadvarArgs::command(),
-        name: "readvar",
-        run: readvar,
-        kind: CommandKind::Attached {
-            archive: Archive::Required,
-            attach: Attach::Any,
-            validate: Validate::Match,
-        },
+fn readvar_read_halted(
+    hubris: &HubrisArchive,
+    core: &mut dyn Core,
+    regions: Option<&BTreeMap<u32, HubrisRegion>>,
+    variables: &[(String, HubrisVariable)],
+) -> Result<Vec<(Vec<u8>, Option<Pointee>)>> {
+    let requests = variables
+        .iter()
+        .map(|(_, variable)| (variable.addr, variable.size))
+        .collect::<Vec<_>>();
+
+    let bufs = core.read_batch(&requests)?;
+
+    //
+    // We are only given regions if we are to dereference pointers.
+    //
+    let Some(regions) = regions else {
+        return Ok(bufs.into_iter().map(|buf| (buf, None)).collect());
+    };
+
+    let mut rval = vec![];
+
+    for ((name, variable), buf) in variables.iter().zip(bufs.into_iter()) {
+        let pointee =
+            readvar_pointee(hubris, core, regions, name, variable, &buf)?;
+        rval.push((buf, pointee));
+    }
+
+    Ok(rval)
+}
+
+///
+/// The value pointed to by a variable, along with its contents.
+///
+type Pointee = (HubrisVariable, Vec<u8>);
+
+///
+/// If the specified variable is a pointer -- or contains only a pointer, as
+/// with `NonNull<T>` or `Option<NonNull<T>>` -- reads the value that it
+/// points to.  Null pointers and pointers outside of known (non-device)
+/// regions are not followed.
+///
+fn readvar_pointee(
+    hubris: &HubrisArchive,
+    core: &mut dyn Core,
+    regions: &BTreeMap<u32, HubrisRegion>,
+    name: &str,
+    variable: &HubrisVariable,
+    buf: &[u8],
+) -> Result<Option<Pointee>> {
+    fn pointer(value: &HubrisValue) -> Option<Ptr> {
+        match value {
+            Value::Ptr(ptr) => Some(*ptr),
+            Value::Enum(e) => e.contents().and_then(pointer),
+            Value::Struct(s) if s.len() == 1 => {
+                s.iter().next().and_then(|(_, v)| pointer(v))
+            }
+            Value::Tuple(t) if t.len() == 1 => pointer(&t[0]),
+            _ => None,
+        }
+    }
+
+    let Some(ptr) = pointer(&hubris.read_value(buf, variable.goff)?) else {
+        humility::warn!("{name}: no pointer to dereference");
+        return Ok(None);
+    };
+
+    let addr = ptr.addr();
+
+    if addr == 0 {
+        humility::warn!("{name}: not dereferencing null pointer");
+        return Ok(None);
+    }
+
+    let goff = ptr.dest_goff(hubris)?;
+    let size = hubris.lookup_type(goff)?.size(hubris)?;
+
+    let valid = match regions.range(..=addr).next_back() {
+        Some((_, region)) => {
+            !region.attr.device
+                && addr as u64 + size as u64
+                    <= region.base as u64 + region.size as u64
+        }
+        None => false,
+    };
+
+    if !valid {
+        humility::warn!(
+            "{name}: not dereferencing 0x{addr:x}: not in a known region"
+        );
+        return Ok(None);
+    }
+
+    let mut buf = vec![0; size];
+    core.read_8(addr, &mut buf)?;
+
+    Ok(Some((HubrisVariable { goff, addr, size }, buf)))
+}
+
+///
+/// The gap between successive reads with `--samples`.
+///
+const READVAR_SAMPLE_GAP: Duration = Duration::from_millis(10);
+
+///
+/// Appends each numeric member of a decoded value, named by its path from the
+/// variable (e.g., `TASK_TABLE[3].generation`).
+///
+fn readvar_leaves(
+    path: &str,
+    value: &serde_json::Value,
+    out: &mut Vec<(String, serde_json::Number)>,
+) {
+    use serde_json::Value as Json;
+
+    match value {
+        Json::Object(members) => {
+            for (k, v) in members {
+                readvar_leaves(&format!("{path}.{k}"), v, out);
+            }
+        }
+        Json::Array(elements) => {
+            for (i, v) in elements.iter().enumerate() {
+                readvar_leaves(&format!("{path}[{i}]"), v, out);
+            }
+        }
+        Json::Number(n) => out.push((path.to_string(), n.clone())),
+        _ => {}
+    }
+}
+
+///
+/// Reads the variables `samples` times, displaying the minimum, maximum and
+/// mean of each of their numeric members.
+///
+fn readvar_samples(
+    hubris: &HubrisArchive,
+    core: &mut dyn Core,
+    variables: &[(String, HubrisVariable)],
+    samples: usize,
+    subargs: &ReadvarArgs,
+) -> Result<()> {
+    if samples == 0 {
+        bail!("must take at least one sample");
+    }
+
+    //
+    // For each variable, the values taken by each of its numeric members.
+    //
+    let mut leaves: Vec<Vec<(String, Vec<serde_json::Number>)>> =
+        vec![vec![]; variables.len()];
+
+    for i in 0..samples {
+        if i > 0 {
+            thread::sleep(READVAR_SAMPLE_GAP);
+        }
+
+        let values =
+            readvar_read(hubris, core, None, variables, true, subargs)?;
+
+        for (((n, v), (buf, _)), leaves) in
+            variables.iter().zip(values.iter()).zip(leaves.iter_mut())
+        {
+            let json = hubris
+                .printjson(buf, v.goff)
+                .with_context(|| format!("failed to decode {n}"))?;
+
+            let mut sample = vec![];
+            readvar_leaves(n, &json, &mut sample);
+
+            if sample.is_empty() {
+                bail!("{n} has no numeric values to sample");
+            }
+
+            //
+            // An enum's members can change from one sample to the next, so
+            // we can't assume that each sample has the same members.
+            //
+            for (path, value) in sample {
+                match leaves.iter_mut().find(|(p, _)| *p == path) {
+                    Some((_, values)) => values.push(value),
+                    None => leaves.push((path, vec![value])),
+                }
+            }
+        }
+    }
+
+    let show = |n: &serde_json::Number| match n.as_u64() {
+        Some(v) if !subargs.decimal => format!("0x{v:x}"),
+        _ => n.to_string(),
+    };
+
+    for ((n, v), leaves) in variables.iter().zip(leaves.iter()) {
+        println!("{n} (0x{:08x}), {samples} samples:", v.addr);
+
+        for (path, values) in leaves {
+            let value = |n: &serde_json::Number| n.as_f64().unwrap_or(0.0);
+
+            let min = values
+                .iter()
+                .min_by(|a, b| value(a).total_cmp(&value(b)))
+                .unwrap();
+            let max = values
+                .iter()
+                .max_by(|a, b| value(a).total_cmp(&value(b)))
+                .unwrap();
+            let mean =
+                values.iter().map(value).sum::<f64>() / values.len() as f64;
+
+            print!(
+                "    {path}: min {}, max {}, mean {mean:.3}",
+                show(min),
+                show(max)
+            );
+
+            if values.len() < samples {
+                print!(" ({} of {samples} samples)", values.len());
+            }
+
+            println!();
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// Magic number identifying a variable captured with `--output`.
+///
+const READVAR_CAPTURE_MAGIC: [u8; 4] = *b"HVAR";
+
+///
+/// Writes captured variables to the specified file.  Each variable is
+/// written as a header -- consisting of a magic number, the object and
+/// offset of the variable's type, its address, its size and the length of
+/// its name, all as little-endian 32-bit words -- followed by its name and
+/// then its contents.
+///
+fn readvar_capture<'a>(
+    path: &str,
+    captured: impl Iterator<Item = (&'a String, &'a HubrisVariable, &'a Vec<u8>)>,
+) -> Result<()> {
+    let mut out = vec![];
+
+    for (name, variable, buf) in captured {
+        out.extend_from_slice(&READVAR_CAPTURE_MAGIC);
+
+        for word in [
+            variable.goff.object,
+            variable.goff.goff as u32,
+            variable.addr,
+            buf.len() as u32,
+            name.len() as u32,
+        ] {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(buf);
+    }
+
+    std::fs::write(path, out)
+        .with_context(|| format!("failed to write capture to \"{path}\""))
+}
+
+///
+/// Reads variables captured with `--output`.  The archive must be the same
+/// as the one used to capture the variables.
+///
+fn readvar_load(
+    hubris: &HubrisArchive,
+    path: &str,
+) -> Result<Vec<(String, HubrisVariable, Vec<u8>)>> {
+    let contents = std::fs::read(path)
+        .with_context(|| format!("failed to read capture \"{path}\""))?;
+
+    let mut rest = &contents[..];
+    let mut captured = vec![];
+
+    fn take<'a>(rest: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+        if rest.len() < len {
+            bail!("capture is truncated");
+        }
+
+        let (taken, remaining) = rest.split_at(len);
+        *rest = remaining;
+        Ok(taken)
+    }
+
+    let word = |bytes: &[u8], ndx: usize| {
+        u32::from_le_bytes(bytes[ndx * 4..(ndx + 1) * 4].try_into().unwrap())
+    };
+
+    while !rest.is_empty() {
+        if take(&mut rest, READVAR_CAPTURE_MAGIC.len())?
+            != READVAR_CAPTURE_MAGIC
+        {
+            bail!("\"{path}\" is not a captured variable");
+        }
+
+        let header = take(&mut rest, 5 * 4)?;
+
+        let goff = HubrisGoff {
+            object: word(header, 0),
+            goff: word(header, 1) as usize,
+        };
+
+        let addr = word(header, 2);
+        let size = word(header, 3) as usize;
+        let len = word(header, 4) as usize;
+        let name = String::from_utf8(take(&mut rest, len)?.to_vec())?;
+        let buf = take(&mut rest, size)?.to_vec();
+
+        if hubris.typesize(goff)? != size {
+            bail!(
+                "size of captured {name} ({size}) doesn't match size of its \
+                type; is this the same archive?"
+            );
+        }
+
+        captured.push((name, HubrisVariable { goff, addr, size }, buf));
+    }
+
+    Ok(captured)
+}
+
+///
+/// Displays variables captured with `--output`.
+///
+fn readvar_replay(
+    hubris: &HubrisArchive,
+    path: &str,
+    subargs: &ReadvarArgs,
+) -> Result<()> {
+    for (name, variable, buf) in readvar_load(hubris, path)? {
+        readvar_print(hubris, &variable, &name, &buf, 0, subargs)?;
+    }
+
+    Ok(())
+}
+
+///
+/// Compares two decoded values member by member, appending a line for each
+/// member that differs.  Members are named by their path from the variable
+/// (e.g., `TASK_TABLE[3].state`).
+///
+fn readvar_diff_value(
+    path: &str,
+    a: &serde_json::Value,
+    b: &serde_json::Value,
+    hex: bool,
+    out: &mut Vec<String>,
+) {
+    use serde_json::Value as Json;
+
+    let show = |v: Option<&Json>| match v {
+        Some(Json::Number(n)) if hex && n.is_u64() => {
+            format!("0x{:x}", n.as_u64().unwrap())
+        }
+        Some(v) => v.to_string(),
+        None => "<absent>".to_string(),
+    };
+
+    match (a, b) {
+        (Json::Object(a), Json::Object(b)) => {
+            let mut keys = a.keys().collect::<Vec<_>>();
+            keys.extend(b.keys().filter(|k| !a.contains_key(*k)));
+
+            for k in keys {
+                let p = format!("{path}.{k}");
+
+                match (a.get(k), b.get(k)) {
+                    (Some(a), Some(b)) => {
+                        readvar_diff_value(&p, a, b, hex, out)
+                    }
+                    (a, b) => {
+                        out.push(format!("{p}: {} -> {}", show(a), show(b)))
+                    }
+                }
+            }
+        }
+        (Json::Array(a), Json::Array(b)) if a.len() == b.len() => {
+            for (i, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                readvar_diff_value(&format!("{path}[{i}]"), a, b, hex, out);
+            }
+        }
+        (a, b) if a != b => {
+            out.push(format!("{path}: {} -> {}", show(Some(a)), show(Some(b))));
+        }
+        _ => {}
+    }
+}
+
+///
+/// Displays the differences between the variables captured in two files
+/// written with `--output`.  Variables are matched by name and compared
+/// member by member; if a variable cannot be decoded, its contents are
+/// instead compared byte by byte.
+///
+fn readvar_diff(
+    hubris: &HubrisArchive,
+    before: &str,
+    after: &str,
+    subargs: &ReadvarArgs,
+) -> Result<()> {
+    let wanted = |name: &str| {
+        subargs.variables.is_empty()
+            || subargs.variables.iter().any(|v| v == name)
+    };
+
+    let before = readvar_load(hubris, before)?;
+    let after = readvar_load(hubris, after)?;
+
+    for (name, _, _) in after.iter().filter(|(n, _, _)| wanted(n)) {
+        if !before.iter().any(|(n, _, _)| n == name) {
+            humility::warn!("{name}: not present in first capture");
+        }
+    }
+
+    for (name, variable, a) in before.iter().filter(|(n, _, _)| wanted(n)) {
+        let Some((_, _, b)) = after.iter().find(|(n, _, _)| n == name) else {
+            humility::warn!("{name}: not present in second capture");
+            continue;
+        };
+
+        let mut diffs = vec![];
+
+        match (
+            hubris.printjson(a, variable.goff),
+            hubris.printjson(b, variable.goff),
+        ) {
+            (Ok(a), Ok(b)) => {
+                readvar_diff_value(name, &a, &b, !subargs.decimal, &mut diffs)
+            }
+            _ => {
+                for (offs, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                    if a != b {
+                        diffs.push(format!(
+                            "{name}+0x{offs:x}: 0x{a:02x} -> 0x{b:02x}"
+                        ));
+                    }
+                }
+            }
+        }
+
+        if diffs.is_empty() {
+            println!("{name} (0x{:08x}): unchanged", variable.addr);
+            continue;
+        }
+
+        println!("{name} (0x{:08x}):", variable.addr);
+
+        for diff in diffs {
+            println!("    {diff}");
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// Parses a pattern given to `--assert-pattern` into the bytes that it
+/// denotes in memory.  The width of the pattern is given by its number of
+/// hex digits (e.g., 0xa5 is a byte, 0xbaddcafe is a word) and, as with the
+/// target, it is little-endian.
+///
+fn readvar_pattern(pattern: &str) -> Result<Vec<u8>> {
+    let digits = pattern
+        .strip_prefix("0x")
+        .or_else(|| pattern.strip_prefix("0X"))
+        .unwrap_or(pattern);
+
+    if digits.is_empty() || digits.len() > 16 {
+        bail!("pattern must be between 1 and 16 hex digits");
+    }
+
+    let value = u64::from_str_radix(digits, 16)
+        .with_context(|| format!("invalid pattern \"{pattern}\""))?;
+
+    Ok(value.to_le_bytes()[..(digits.len() + 1) / 2].to_vec())
+}
+
+///
+/// Checks the contents of each variable against the (repeating) pattern,
+/// reporting each as clean or dirty, and returning the number that are dirty.
+///
+fn readvar_assert(
+    variables: &[(String, HubrisVariable)],
+    values: &[(Vec<u8>, Option<Pointee>)],
+    pattern: &[u8],
+) -> usize {
+    let mut dirty = 0;
+
+    for ((n, v), (buf, _)) in variables.iter().zip(values.iter()) {
+        let expected = pattern.iter().cycle();
+
+        match buf.iter().zip(expected).position(|(b, p)| b != p) {
+            None => println!("{} (0x{:08x}): clean", n, v.addr),
+            Some(offs) => {
+                println!(
+                    "{} (0x{:08x}): dirty at offset 0x{:x} \
+                    (found 0x{:02x}, expected 0x{:02x})",
+                    n,
+                    v.addr,
+                    offs,
+                    buf[offs],
+                    pattern[offs % pattern.len()]
+                );
+                dirty += 1;
+            }
+        }
+    }
+
+    dirty
+}
+
+fn readvar_print(
+    hubris: &HubrisArchive,
+    variable: &HubrisVariable,
+    name: &str,
+    buf: &[u8],
+    indent: usize,
+    subargs: &ReadvarArgs,
+) -> Result<()> {
+    let ty = hubris.lookup_type(variable.goff)?.name(hubris)?;
+
+    if subargs.raw {
+        if subargs.show_type {
+            println!(
+                "{:indent$}{}: {} (0x{:08x}):",
+                "", name, ty, variable.addr
+            );
+        } else {
+            println!("{:indent$}{} (0x{:08x}):", "", name, variable.addr);
+        }
+
+        let mut dumper = Dumper::new();
+        dumper.indent = indent;
+        dumper.dump(buf, variable.addr);
+        return Ok(());
+    }
+
+    if subargs.json {
+        let value = serde_json::json!({
+            "name": name,
+            "type": ty,
+            "addr": variable.addr,
+            "size": variable.size,
+            "value": readvar_value(hubris, buf, variable, subargs)?
+                .to_json(hubris)?,
+        });
+
+        println!("{}", value);
+        return Ok(());
+    }
+
+    let (fmt, compact) = readvar_format(subargs, indent);
+    let ty = fmt.type_name(&ty);
+
+    //
+    // Large values can span many lines; we print each line as soon as it has
+    // been formatted rather than assembling the value as a single string.
+    // The first line follows the name of the variable.
+    //
+    let mut first = true;
+
+    let mut emit = |line: &str| {
+        if !first {
+            println!("{line}");
+        } else if subargs.show_type {
+            println!(
+                "{:indent$}{}: {} (0x{:08x}) = {}",
+                "", name, ty, variable.addr, line
+            );
+        } else {
+            println!(
+                "{:indent$}{} (0x{:08x}) = {}",
+                "", name, variable.addr, line
+            );
+        }
+
+        first = false;
+    };
+
+    if compact || subargs.follow_vec {
+        let value = readvar_value(hubris, buf, variable, subargs)?;
+        let mut out = HubrisLineWriter::new(&mut emit);
+
+        if compact {
+            format_compact(&value, hubris, fmt, &mut out)?;
+        } else {
+            value.format(hubris, fmt, &mut out)?;
+        }
+
+        out.finish();
+    } else {
+        hubris.printfmt_lines(buf, variable.goff, fmt, &mut emit)?;
+    }
+
+    Ok(())
+}
+
+///
+/// Decodes the value of a variable, truncating any fixed-capacity vectors
+/// within it to their live elements if `--follow-vec` has been specified.
+///
+fn readvar_value(
+    hubris: &HubrisArchive,
+    buf: &[u8],
+    variable: &HubrisVariable,
+    subargs: &ReadvarArgs,
+) -> Result<Value> {
+    let mut value = hubris.read_value(buf, variable.goff)?;
+
+    if subargs.follow_vec {
+        value.follow_vecs();
+    }
+
+    Ok(value)
+}
+
+///
+/// Determines how values are to be displayed, returning the format along
+/// with whether the compact format has been requested.
+///
+fn readvar_format(
+    subargs: &ReadvarArgs,
+    indent: usize,
+) -> (HubrisPrintFormat<'_>, bool) {
+    let hex = !subargs.decimal;
+
+    let mut fmt = HubrisPrintFormat {
+        indent,
+        indent_width: subargs.indent,
+        newline: true,
+        hex,
+        interpret_as_c_string: subargs.as_c_string,
+        interpret_as_text: subargs.string,
+        skip_default: subargs.brief,
+        short_names: subargs.short_names,
+        ..HubrisPrintFormat::default()
+    };
+
+    //
+    // A unit implies a scale (of 1, if none has been specified).
+    //
+    if subargs.scale.is_some() || subargs.unit.is_some() {
+        fmt.scale = Some(subargs.scale.unwrap_or(1.0));
+        fmt.unit = subargs.unit.as_deref();
+    }
+
+    let mut compact = false;
+
+    for spec in &subargs.format {
+        match spec {
+            FormatSpec::Hex => fmt.hex = true,
+            FormatSpec::Dec => fmt.hex = false,
+            FormatSpec::Bin => fmt.binary = true,
+            FormatSpec::Cstr => fmt.interpret_as_c_string = true,
+            FormatSpec::Str => fmt.interpret_as_text = true,
+            FormatSpec::Oneline => fmt.newline = false,
+            FormatSpec::Compact => {
+                fmt.newline = false;
+                compact = true;
+            }
+        }
+    }
+
+    (fmt, compact)
+}
+
+///
+/// Displays structures -- and the structures within arrays of structures --
+/// as a table, with a row for each structure and a column for each member.
+/// Each member is displayed on a single line.
+///
+fn readvar_table(
+    hubris: &HubrisArchive,
+    variables: &[(String, HubrisVariable)],
+    values: &[(Vec<u8>, Option<Pointee>)],
+    subargs: &ReadvarArgs,
+) -> Result<()> {
+    let (fmt, _) = readvar_format(subargs, 0);
+    let fmt = HubrisPrintFormat { newline: false, ..fmt };
+
+    let mut columns: Vec<String> = vec![];
+    let mut rows = vec![];
+
+    for ((name, v), (buf, _)) in variables.iter().zip(values.iter()) {
+        let value = readvar_value(hubris, buf, v, subargs)?;
+
+        let elements = match &value {
+            Value::Array(a) => a
+                .iter()
+                .enumerate()
+                .map(|(i, e)| (format!("{name}[{i}]"), e))
+                .collect::<Vec<_>>(),
+            value => vec![(name.clone(), value)],
+        };
+
+        for (label, element) in elements {
+            let members = match element {
+                Value::Struct(s) => s
+                    .iter()
+                    .map(|(m, v)| (m.to_string(), v))
+                    .collect::<Vec<_>>(),
+                Value::Tuple(t) => t
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (i.to_string(), v))
+                    .collect(),
+                _ => bail!(
+                    "{label} is not a structure; --table requires structures \
+                    or arrays of structures"
+                ),
+            };
+
+            let mut cells = vec![];
+
+            for (member, value) in members {
+                if !columns.contains(&member) {
+                    columns.push(member.clone());
+                }
+
+                let mut out = vec![];
+                value.format(hubris, fmt, &mut out)?;
+                cells.push((member, String::from_utf8_lossy(&out).to_string()));
+            }
+
+            rows.push((label, cells));
+        }
+    }
+
+    let cell = |cells: &[(String, String)], column: &str| {
+        cells
+            .iter()
+            .find(|(c, _)| c == column)
+            .map_or("-".to_string(), |(_, v)| v.clone())
+    };
+
+    let width = rows.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+
+    let widths = columns
+        .iter()
+        .map(|c| {
+            rows.iter()
+                .map(|(_, cells)| cell(cells, c).len())
+                .chain(std::iter::once(c.len()))
+                .max()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let mut header = format!("{:width$}", "");
+
+    for (c, w) in columns.iter().zip(widths.iter()) {
+        header += &format!(" {c:w$}");
+    }
+
+    println!("{}", header.trim_end());
+
+    for (label, cells) in &rows {
+        let mut line = format!("{label:width$}");
+
+        for (c, w) in columns.iter().zip(widths.iter()) {
+            line += &format!(" {:w$}", cell(cells, c));
+        }
+
+        println!("{}", line.trim_end());
+    }
+
+    Ok(())
+}
+
+///
+/// Resolves an expression of the form "Type@address" (optionally followed
+/// by a path of members and/or array subscripts, as with variables) into a
+/// variable of the named structure at the specified address.
+///
+fn readvar_typed(
+    hubris: &HubrisArchive,
+    ty: &str,
+    rest: &str,
+) -> Result<(String, HubrisVariable)> {
+    let (addr, path) = match rest.find(['.', '[']) {
+        Some(ndx) => rest.split_at(ndx),
+        None => (rest, ""),
+    };
+
+    let addr = parse_int::parse::<u32>(addr)
+        .with_context(|| format!("invalid address \"{addr}\""))?;
+
+    let s = hubris.lookup_struct_byname(ty)?;
+    let (offset, goff) = hubris.lookup_member_path(s.goff, path)?;
+
+    let variable = HubrisVariable {
+        goff,
+        addr: addr + offset as u32,
+        size: hubris.typesize(goff)?,
+    };
+
+    Ok((format!("{ty}@0x{addr:x}{path}"), variable))
+}
+
+///
+/// Looks up the specified variable, returning all matching variables.  The
+/// variable may be followed by a path of members and/or array subscripts
+/// (e.g., "CONFIG.thresholds[2]"), in which case we return only the
+/// designated member.
+///
+fn readvar_lookup(
+    hubris: &HubrisArchive,
+    variable: &str,
+    task: Option<HubrisTask>,
+) -> Result<Vec<(String, HubrisVariable)>> {
+    if let Some((ty, rest)) = variable.split_once('@') {
+        return Ok(vec![readvar_typed(hubris, ty, rest)?]);
+    }
+
+    //
+    // No variable name begins with a digit, so if we can parse this as a
+    // number, it is the address of a variable (e.g., as displayed by -l).
+    //
+    if let Ok(addr) = parse_int::parse::<u32>(variable) {
+        let matches = hubris
+            .qualified_variables()
+            .filter(|&(_, v)| v.addr == addr)
+            .filter(|&(_, v)| {
+                task.map_or(true, |t| HubrisTask::from(v.goff) == t)
+            })
+            .map(|(n, v)| (n.to_string(), *v))
+            .collect::<Vec<_>>();
+
+        if matches.is_empty() {
+            bail!("no variable at address 0x{addr:x}; use \"-l\" to list");
+        }
+
+        return Ok(matches);
+    }
+
+    fn match_exact(n: &str, v: &str) -> bool {
+        n == v
+    }
+
+    fn match_suffix(n: &str, v: &str) -> bool {
+        let mut suffix = "::".to_string();
+        suffix.push_str(v);
+
+        n == v || n.ends_with(&suffix)
+    }
+
+    let (variable, path) = match variable.find(['.', '[']) {
+        Some(ndx) => variable.split_at(ndx),
+        None => (variable, ""),
+    };
+
+    let m = if variable.contains("::") { match_exact } else { match_suffix };
+
+    let matches = hubris
+        .qualified_variables()
+        .filter(|&(n, _)| m(n, variable))
+        .filter(|&(_, v)| task.map_or(true, |t| HubrisTask::from(v.goff) == t))
+        .map(|(n, v)| -> Result<(String, HubrisVariable)> {
+            let (offset, goff) = hubris.lookup_member_path(v.goff, path)?;
+
+            let member = HubrisVariable {
+                goff,
+                addr: v.addr + offset as u32,
+                size: hubris.typesize(goff)?,
+            };
+
+            Ok((format!("{n}{path}"), member))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if matches.is_empty() {
+        if let Some(task) = task {
+            let module = hubris.lookup_module(task)?;
+
+            bail!(
+                "variable '{variable}' not found in task {}; \
+                use \"-l --task {}\" to list",
+                module.name,
+                module.name
+            );
+        }
+
+        let suggestions = readvar_suggest(hubris, variable);
+
+        if !suggestions.is_empty() {
+            bail!(
+                "variable '{variable}' not found; did you mean {}?",
+                suggestions.join(", ")
+            );
+        }
+
+        bail!("variable '{variable}' not found; use \"-l\" to list");
+    }
+
+    Ok(matches)
+}
+
+///
+/// Returns the edit (Levenshtein) distance between two strings.
+///
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { prev } else { prev + 1 };
+            prev = row[j + 1];
+            row[j + 1] = cost.min(row[j] + 1).min(prev + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+///
+/// Returns up to three variable names that are close to the specified
+/// (misspelled) variable.  If the variable is qualified, it is compared
+/// against qualified names; otherwise, it is compared against the last
+/// component of each name.
+///
+fn readvar_suggest(hubris: &HubrisArchive, variable: &str) -> Vec<String> {
+    let qualified = variable.contains("::");
+    let max = std::cmp::max(2, variable.len() / 3);
+
+    let mut candidates = hubris
+        .qualified_variables()
+        .map(|(n, _)| {
+            let name = match (qualified, n.rsplit_once("::")) {
+                (false, Some((_, name))) => name,
+                _ => n,
+            };
+
+            (distance(variable, name), name.to_string())
+        })
+        .filter(|(d, _)| *d <= max)
+        .collect::<Vec<_>>();
+
+    candidates.sort();
+    candidates.dedup_by(|a, b| a.1 == b.1);
+
+    candidates.into_iter().take(3).map(|(_, name)| name).collect()
+}
+
+///
+/// Replaces each (array) variable with the window of its elements designated
+/// by `--start` and `--count`, so that only those elements are read.
+///
+fn readvar_window(
+    hubris: &HubrisArchive,
+    variables: Vec<(String, HubrisVariable)>,
+    subargs: &ReadvarArgs,
+) -> Result<Vec<(String, HubrisVariable)>> {
+    let mut rval = vec![];
+
+    for (name, variable) in variables {
+        let array = hubris.lookup_array(variable.goff).map_err(|_| {
+            anyhow!("{name} is not an array; can't use --start or --count")
+        })?;
+
+        let start = subargs.start.unwrap_or(0);
+        let count = subargs.count.unwrap_or(array.count.saturating_sub(start));
+
+        if start + count > array.count {
+            bail!(
+                "elements {start}..{} out of range: {name} has {} elements",
+                start + count,
+                array.count
+            );
+        }
+
+        let size = hubris.typesize(array.goff)?;
+
+        for i in start..start + count {
+            let element = HubrisVariable {
+                goff: array.goff,
+                addr: variable.addr + (i * size) as u32,
+                size,
+            };
+
+            rval.push((format!("{name}[{i}]"), element));
+        }
+    }
+
+    Ok(rval)
+}
+
+///
+/// Lists all variables for which the specified matcher (which is passed the
+/// module name and the variable name) returns true.
+///
+fn readvar_list(
+    hubris: &HubrisArchive,
+    matcher: impl Fn(&str, &str) -> bool,
+    subargs: &ReadvarArgs,
+) -> Result<()> {
+    let mut all = hubris
+        .variables_table()?
+        .into_iter()
+        .filter(|v| matcher(&v.module, &v.name))
+        .collect::<Vec<_>>();
+
+    match subargs.sort {
+        Some(ListOrder::Addr) => all.sort_by_key(|v| v.addr),
+        Some(ListOrder::Size) => all.sort_by_key(|v| v.size),
+        Some(ListOrder::Name) => all.sort_by(|a, b| a.name.cmp(&b.name)),
+        None => {}
+    }
+
+    if subargs.json {
+        println!("{}", serde_json::to_string(&all)?);
+        return Ok(());
+    }
+
+    println!("{:18} {:<42} {:<10} SIZE", "MODULE", "VARIABLE", "ADDR");
+
+    for v in &all {
+        println!(
+            "{:18} {:<42} 0x{:08x} {:<}",
+            v.module, v.name, v.addr, v.size
+        );
+    }
+
+    Ok(())
+}
+
+///
+/// Returns the address and (exclusive) end of the first portion of the
+/// variable that lies outside of the specified regions, if any.  A variable
+/// may span adjacent regions.
+///
+fn readvar_unmapped(
+    regions: &BTreeMap<u32, HubrisRegion>,
+    variable: &HubrisVariable,
+) -> Option<(u64, u64)> {
+    let end = variable.addr as u64 + variable.size as u64;
+    let mut addr = variable.addr as u64;
+
+    while addr < end {
+        match HubrisArchive::region_at(regions, addr as u32) {
+            Some(region) => addr = region.base as u64 + region.size as u64,
+            None => {
+                let next = regions
+                    .range(addr as u32..)
+                    .next()
+                    .map_or(end, |(&base, _)| end.min(base as u64));
+
+                return Some((addr, next));
+            }
+        }
+    }
+
+    None
+}
+
+///
+/// With `--json`, reports an error as an object on stdout (rather than as
+/// prose on stderr), so that a script can distinguish a variable that can't
+/// be found (`lookup`) from a failure to read it from the target (`read`) or
+/// to decode it (`decode`).  Outside of the REPL, we return an exit status
+/// without a message, lest the error also be printed as prose.
+///
+fn readvar_error(
+    variable: Option<&str>,
+    kind: &str,
+    err: anyhow::Error,
+    interactive: bool,
+) -> Result<()> {
+    let value = serde_json::json!({
+        "error": format!("{err:#}"),
+        "kind": kind,
+        "variable": variable,
+    });
+
+    println!("{}", value);
+
+    if !interactive {
+        return Err(ExitStatus { status: 1, msg: None }.into());
+    }
+
+    Err(err)
+}
+
+///
+/// Restores the previously selected hart when dropped.
+///
+struct HartGuard<'a> {
+    core: &'a mut dyn Core,
+    previous: usize,
+}
+
+impl<'a> HartGuard<'a> {
+    fn select(core: &'a mut dyn Core, hart: usize) -> Result<Self> {
+        let previous = core.selected_core();
+        core.select_core(hart)?;
+        Ok(Self { core, previous })
+    }
+}
+
+impl Drop for HartGuard<'_> {
+    fn drop(&mut self) {
+        if let Err(err) = self.core.select_core(self.previous) {
+            humility::warn!("failed to restore hart {}: {err}", self.previous);
+        }
+    }
+}
+
+fn readvar(context: &mut ExecutionContext) -> Result<()> {
+    let core = &mut **context.core.as_mut().unwrap();
+    let Subcommand::Other(subargs) = context.cli.cmd.as_ref().unwrap();
+    let hubris = context.archive.as_ref().unwrap();
+    let interactive = context.is_interactive;
+
+    let subargs = ReadvarArgs::try_parse_from(subargs)?;
+
+    //
+    // If we have been given a standalone ELF object, we use it (rather than
+    // the archive) to look up and interpret variables -- but we continue to
+    // use the archive to determine the target's memory regions.
+    //
+    let elf = match &subargs.elf {
+        Some(elf) => {
+            let mut symbols = HubrisArchive::new()?;
+
+            symbols
+                .load_elf(elf)
+                .with_context(|| format!("failed to load ELF \"{elf}\""))?;
+
+            Some(symbols)
+        }
+        None => None,
+    };
+
+    let archive = hubris;
+    let hubris = elf.as_ref().unwrap_or(archive);
+
+    let task = match &subargs.task {
+        Some(name) => match hubris.lookup_task(name) {
+            Some(task) => Some(*task),
+            None => bail!("unknown task \"{name}\""),
+        },
+        None => None,
+    };
+
+    if subargs.list {
+        if subargs.variables.len() > 1 {
+            bail!("only one pattern may be specified with \"-l\"");
+        }
+
+        let pattern = subargs.variables.first();
+
+        let regex = match (pattern, subargs.regex) {
+            (Some(pattern), true) => Some(Regex::new(pattern)?),
+            _ => None,
+        };
+
+        let matcher = |module: &str, name: &str| match (pattern, &regex) {
+            _ if subargs.task.as_ref().map_or(false, |t| t != module) => false,
+            (None, _) => true,
+            (Some(_), Some(re)) => re.is_match(module) || re.is_match(name),
+            (Some(pattern), None) => {
+                module.contains(pattern.as_str())
+                    || name.contains(pattern.as_str())
+            }
+        };
+
+        return readvar_list(hubris, matcher, &subargs);
+    }
+
+    if let Some(path) = &subargs.replay {
+        return readvar_replay(hubris, path, &subargs);
+    }
+
+    if let [before, after] = &subargs.diff[..] {
+        return readvar_diff(hubris, before, after, &subargs);
+    }
+
+    if subargs.variables.is_empty() {
+        bail!("expected variable (use \"-l\" to list)");
+    }
+
+    //
+    // The core is shared with any subsequent command (e.g., in the REPL), so
+    // if we select a hart, the guard restores the previously selected one
+    // however we return.
+    //
+    let mut guard;
+
+    let core = match subargs.hart {
+        Some(hart) => {
+            let ncores = core.num_cores();
+
+            if hart >= ncores {
+                bail!(
+                    "hart {hart} is out of range: target has {ncores} hart{}",
+                    if ncores == 1 { "" } else { "s" }
+                );
+            }
+
+            guard = HartGuard::select(core, hart)?;
+            &mut *guard.core
+        }
+        None => core,
+    };
+
+    let pattern = match &subargs.assert_pattern {
+        Some(pattern) => Some(readvar_pattern(pattern)?),
+        None => None,
+    };
+
+    let mut matches = vec![];
+
+    for variable in &subargs.variables {
+        match readvar_lookup(hubris, variable, task) {
+            Ok(m) => matches.extend(m),
+            Err(err) if subargs.json => {
+                return readvar_error(
+                    Some(variable),
+                    "lookup",
+                    err,
+                    interactive,
+                );
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    if subargs.start.is_some() || subargs.count.is_some() {
+        matches = readvar_window(hubris, matches, &subargs)?;
+    }
+
+    //
+    // Before reading, we check that each variable lies entirely within the
+    // target's known regions:  stale debug information (or a partial dump)
+    // would otherwise result in a confusing error -- or in silently reading
+    // garbage.  We don't read regions for archives or network targets, and
+    // if we can't read them, we skip the check.
+    //
+    let known = if core.is_archive() || core.is_net() {
+        None
+    } else {
+        archive.regions(core).ok()
+    };
+
+    if let Some(known) = &known {
+        for (n, v) in &matches {
+            let Some((start, end)) = readvar_unmapped(known, v) else {
+                continue;
+            };
+
+            let err = anyhow!(
+                "{n} (0x{:x}, {} bytes) spans unmapped memory at 0x{start:x} \
+                (0x{:x} bytes not in any known region)",
+                v.addr,
+                v.size,
+                end - start
+            );
+
+            if subargs.json {
+                return readvar_error(Some(n), "read", err, interactive);
+            }
+
+            return Err(err);
+        }
+    }
+
+    if let Some(value) = &subargs.write {
+        if matches.len() > 1 {
+            let names =
+                matches.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>();
+
+            bail!(
+                "can only write to a single variable; matched {}",
+                names.join(", ")
+            );
+        }
+
+        let (n, v) = &matches[0];
+        let regions = archive.regions(core)?;
+        readvar_write(hubris, core, &regions, v, n, value, &subargs)?;
+    }
+
+    let regions =
+        if subargs.deref { Some(archive.regions(core)?) } else { None };
+
+    //
+    // A variable in device memory (e.g., a peripheral's shadow register) may
+    // change even while the target is halted, so we never want to read it
+    // from a cache.  Determining this requires the target's regions, which
+    // we don't bother to read for dumps, archives or network targets (none
+    // of which has a cache); if we couldn't read them, we assume that no
+    // variable is in device memory.
+    //
+    let mut volatile = subargs.volatile;
+    let cacheless = core.is_dump() || core.is_archive() || core.is_net();
+
+    if !volatile && !cacheless {
+        let kind = HubrisRegionKind::Device;
+
+        if let Ok(device) = archive.regions_by_kind(core, kind) {
+            volatile = matches.iter().any(|(_, v)| {
+                HubrisArchive::region_at(&device, v.addr).is_some()
+            });
+        }
+    }
+
+    if let Some(samples) = subargs.samples {
+        return readvar_samples(hubris, core, &matches, samples, &subargs);
+    }
+
+    let mut dirty = 0;
+
+    if subargs.watch {
+        //
+        // We want to be sure that we don't leave the target halted if we are
+        // interrupted, so we catch Ctrl-C and only stop between reads.
+        //
+        let done = humility_cmd::interrupt_flag()?;
+
+        let interval = Duration::from_millis(subargs.interval);
+
+        while !done.load(Ordering::SeqCst) {
+            let values = readvar_read(
+                hubris,
+                core,
+                regions.as_ref(),
+                &matches,
+                volatile,
+                &subargs,
+            )?;
+            let now = chrono::Local::now();
+
+            for ((n, v), (buf, pointee)) in matches.iter().zip(values.iter()) {
+                print!("{} ", now.format("%Y-%m-%d %H:%M:%S%.3f"));
+                readvar_print(hubris, v, n, buf, 0, &subargs)?;
+
+                if let Some((pv, pbuf)) = pointee {
+                    print!("{} ", now.format("%Y-%m-%d %H:%M:%S%.3f"));
+                    readvar_print(
+                        hubris,
+                        pv,
+                        &format!("*{n}"),
+                        pbuf,
+                        4,
+                        &subargs,
+                    )?;
+                }
+            }
+
+            thread::sleep(interval);
+        }
+    } else {
+        let values = match readvar_read(
+            hubris,
+            core,
+            regions.as_ref(),
+            &matches,
+            volatile,
+            &subargs,
+        ) {
+            Ok(values) => values,
+            Err(err) if subargs.json => {
+                return readvar_error(None, "read", err, interactive);
+            }
+            Err(err) => return Err(err),
+        };
+
+        if let Some(path) = &subargs.output {
+            let captured = matches.iter().zip(values.iter());
+            readvar_capture(
+                path,
+                captured.map(|((n, v), (buf, _))| (n, v, buf)),
+            )?;
+            humility::msg!("captured {} variable(s) to {path}", matches.len());
+        }
+
+        if let Some(pattern) = &pattern {
+            dirty = readvar_assert(&matches, &values, pattern);
+        } else if subargs.table {
+            readvar_table(hubris, &matches, &values, &subargs)?;
+        } else {
+            for ((n, v), (buf, pointee)) in matches.iter().zip(values.iter()) {
+                let mut rval = readvar_print(hubris, v, n, buf, 0, &subargs);
+
+                if let (true, Some((pv, pbuf))) = (rval.is_ok(), pointee) {
+                    let n = format!("*{n}");
+                    rval = readvar_print(hubris, pv, &n, pbuf, 4, &subargs);
+                }
+
+                match rval {
+                    Err(err) if subargs.json => {
+                        return readvar_error(
+                            Some(n),
+                            "decode",
+                            err,
+                            interactive,
+                        );
+                    }
+                    rval => rval?,
+                }
+            }
+        }
+    }
+
+    if subargs.leave_halted {
+        humility::msg!("leaving target halted");
+    }
+
+    if dirty > 0 {
+        bail!("{} of {} variable(s) dirty", dirty, matches.len());
+    }
+
+    Ok(())
+}
+
+pub fn init() -> Command {
+    Command {
+        app: ReadvarArgs::command(),
+        name: "readvar",
+        run: readvar,
+        kind: CommandKind::Attached {
+            archive: Archive::Required,
+            attach: Attach::Any,
+            validate: Validate::Match,
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use humility::core::HaltInfo;
+    use humility_arch_arm::ARMRegister;
+    use std::path::Path;
+    use std::time::Duration;
+
+    ///
+    /// A core that tracks its halt count the way that `ProbeCore` does, and
+    /// that can be halted out from under us (as if by a debugger).
+    ///
+    #[derive(Default)]
+    struct MockCore {
+        halts: u32,
+        external: bool,
+        halt_calls: u32,
+    }
+
+    impl Core for MockCore {
+        fn info(&self) -> (String, Option<String>) {
+            ("mock".to_string(), None)
+        }
+
+        fn read_8(&mut self, _addr: u32, data: &mut [u8]) -> Result<()> {
+            data.fill(0);
+            Ok(())
+        }
+
+        fn read_reg(&mut self, _reg: ARMRegister) -> Result<u32> {
+            Ok(0)
+        }
+
+        fn write_reg(&mut self, _reg: ARMRegister, _value: u32) -> Result<()> {
+            Ok(())
+        }
+
+        fn init_swv(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn read_swv(&mut self) -> Result<Vec<u8>> {
+            Ok(vec![])
+        }
+
+        fn write_8(&mut self, _addr: u32, _data: &[u8]) -> Result<()> {
+            Ok(())
+        }
+
+        fn halt(&mut self) -> Result<()> {
+            self.halts += 1;
+            self.halt_calls += 1;
+            Ok(())
+        }
+
+        fn halt_timeout(&mut self, _dur: Duration) -> Result<HaltInfo> {
+            self.halt()?;
+            Ok(HaltInfo::default())
+        }
+
+        fn run(&mut self) -> Result<()> {
+            if self.halts == 0 {
+                bail!("run without halt");
+            }
+
+            self.halts -= 1;
+            Ok(())
+        }
+
+        fn step(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn is_halted(&mut self) -> Result<bool> {
+            Ok(self.external || self.halts > 0)
+        }
+
+        fn load(&mut self, _path: &Path) -> Result<()> {
+            Ok(())
+        }
+
+        fn reset(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn reset_and_halt(&mut self, _dur: Duration) -> Result<()> {
+            Ok(())
+        }
+
+        fn wait_for_halt(&mut self, _dur: Duration) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    fn read(core: &mut MockCore) {
+        let hubris = HubrisArchive::new().unwrap();
+        let subargs = ReadvarArgs::try_parse_from(["readvar", "X"]).unwrap();
+        let variable = HubrisVariable {
+            goff: HubrisGoff { object: 0, goff: 0 },
+            addr: 0x2000_0000,
+            size: 4,
+        };
+
+        let variables = [("X".to_string(), variable)];
+        readvar_read(&hubris, core, None, &variables, false, &subargs).unwrap();
+    }
+
+    #[test]
+    fn test_readvar_read_running() {
+        let mut core = MockCore::default();
+
+        read(&mut core);
+        read(&mut core);
+
+        assert_eq!(core.halt_calls, 2);
+        assert_eq!(core.halts, 0);
+    }
+
+    fn store(core: &mut MockCore, args: &[&str]) {
+        let subargs = ReadvarArgs::try_parse_from(args).unwrap();
+        let variable = HubrisVariable {
+            goff: HubrisGoff { object: 0, goff: 0 },
+            addr: 0x2000_0000,
+            size: 4,
+        };
+
+        readvar_store(core, &variable, "X", &[0; 4], &subargs).unwrap();
+    }
+
+    #[test]
+    fn test_readvar_store() {
+        let mut core = MockCore::default();
+
+        store(&mut core, &["readvar", "X", "--write", "0"]);
+
+        assert_eq!(core.halt_calls, 1);
+        assert_eq!(core.halts, 0);
+
+        //
+        // A target that was already halted should be left that way -- as
+        // should a running one if we have been asked to leave it halted.
+        //
+        let mut core = MockCore { external: true, ..Default::default() };
+
+        store(&mut core, &["readvar", "X", "--write", "0"]);
+
+        assert_eq!(core.halt_calls, 0);
+        assert_eq!(core.halts, 0);
+
+        let mut core = MockCore::default();
+
+        store(&mut core, &["readvar", "X", "--write", "0", "--leave-halted"]);
+
+        assert_eq!(core.halt_calls, 1);
+        assert_eq!(core.halts, 1);
+    }
+
+    #[test]
+    fn test_readvar_read_halted() {
+        //
+        // A target that we halted ourselves (e.g., with `halt` in the REPL)
+        // should need only one run to resume it.
+        //
+        let mut core = MockCore { halts: 1, ..Default::default() };
+
+        read(&mut core);
+        read(&mut core);
+
+        assert_eq!(core.halt_calls, 0);
+        assert_eq!(core.halts, 1);
+
+        //
+        // A target halted by an external debugger should be left with no
+        // halts of our own outstanding.
+        //
+        let mut core = MockCore { external: true, ..Default::default() };
+
+        read(&mut core);
+
+        assert_eq!(core.halt_calls, 0);
+        assert_eq!(core.halts, 0);
     }
 }
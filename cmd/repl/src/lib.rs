@@ -49,6 +49,15 @@
 //! in the background; your code is still running in the background while you
 //! use the repl!
 //!
+//! Tab completes subcommands and their options.  For `readvar`, it also
+//! completes the names of variables in the archive, matching either the
+//! beginning of a variable's fully qualified name or the beginning of its
+//! last component (e.g., `TIC` will complete to `kern::arch::arm_m::TICKS`).
+//!
+//! Following a variable name with a dot completes the names of the members
+//! of its type (e.g., `CONFIG.` will complete to `CONFIG.thresholds` and the
+//! variable's other members).
+//!
 //! Finally, as you can see, `quit` will quit the repl. There is also a
 //! `history` command, which will show you recent commands you've put into the
 //! prompt.
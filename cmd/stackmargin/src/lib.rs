@@ -5,9 +5,13 @@
 //! ## `humility stackmargin`
 //!
 //! `humility stackmargin` calculates and print stack margins by task. The
-//! margin is determined by walking up each stack, looking for the first
-//! word that does not contain the uninitialized pattern (`0xbaddcafe`),
-//! from which it infers maximum depth, and therefore margin:
+//! margin is determined by walking up each stack from its base (that is,
+//! from its deepest possible point), looking for the first word that does
+//! not contain the uninitialized pattern (`0xbaddcafe`), from which it
+//! infers maximum depth, and therefore margin.  Because the walk begins at
+//! the base of the stack, the maximum depth is a high-water mark:  a stack
+//! that has since shrunk (leaving stale, dirty words above untouched ones)
+//! will still report the deepest word that was ever written:
 //!
 //! ```console
 //! $ humility -d ./hubris.core.10 stackmargin
@@ -22,69 +26,697 @@
 //!  6 idle               0x20002800        256        104        152
 //! ```
 //!
+//! If the kernel paints uninitialized stack with a different pattern, and the
+//! archive declares it (as `stack-fill` in the kernel's configuration), that
+//! pattern is used instead; in either case, the pattern can be specified with
+//! `--pattern` (e.g., `--pattern 0xdeadbeef`).
+//!
+//! To display the margin for a single task, specify the task by name or by
+//! its task index:
+//!
+//! ```console
+//! $ humility -d ./hubris.core.10 stackmargin ping
+//! humility: attached to dump
+//! ID TASK                STACKBASE  STACKSIZE   MAXDEPTH     MARGIN
+//!  4 ping               0x20002000        512        224        288
+//! ```
+//!
+//! To additionally display each margin as a percentage of its stack size,
+//! use `--percent`.
+//!
+//! To additionally display a bar showing the proportion of each stack that
+//! has been used, use `--bars`.  (Bars are drawn with Unicode block elements
+//! in a UTF-8 locale, and with `#` and `-` otherwise or if `NO_COLOR` is
+//! set.)
+//!
+//! To display the address of the deepest word that has been written in each
+//! stack (e.g., to look it up in a linker map), use `--show-addr`; this is
+//! also included as `deepest` in JSON and YAML output:
+//!
+//! ```console
+//! $ humility -d ./hubris.core.10 stackmargin --show-addr ping
+//! humility: attached to dump
+//! ID TASK                STACKBASE  STACKSIZE   MAXDEPTH     MARGIN    DEEPEST
+//!  4 ping               0x20002000        512        224        288 0x20002120
+//! ```
+//!
+//! To get the margins as JSON (e.g., for consumption by `jq` or by CI), use
+//! the `--json` option; this will print an array of objects with `id`,
+//! `task`, `stackbase`, `stacksize`, `maxdepth` and `margin` members.  (Tasks
+//! whose stacks cannot be read -- e.g., the supervisor over the network --
+//! will have `null` for all but `id` and `task`.)
+//!
+//! Similarly, to get the margins as YAML, use `--yaml`; this will print a
+//! sequence of mappings with the same members as the JSON output.
+//!
+//! When attached over the network, the supervisor's stack generally cannot be
+//! read and is displayed as unknown; for network configurations that can
+//! read it, use `--force-supervisor` to attempt the read anyway.  (If the
+//! read fails, the supervisor will still be displayed as unknown.)
+//!
+//! To get the margins as comma-separated values (e.g., for import into a
+//! spreadsheet), use `--csv`; this will print a header row followed by a row
+//! per task, with the stack base in hexadecimal and sizes in decimal:
+//!
+//! ```console
+//! $ humility -d ./hubris.core.10 stackmargin --csv
+//! humility: attached to dump
+//! id,task,stackbase,stacksize,maxdepth,margin
+//! 0,jefe,0x20001000,1024,768,256
+//! 1,rcc_driver,0x20001400,1024,176,848
+//! 2,usart_driver,0x20001800,1024,216,808
+//! 3,user_leds,0x20001c00,1024,208,816
+//! 4,ping,0x20002000,512,224,288
+//! 5,pong,0x20002400,1024,208,816
+//! 6,idle,0x20002800,256,104,152
+//! ```
+//!
+//! For monitoring, use `--prometheus` to get the margins in the Prometheus
+//! text exposition format, with gauges for each task's stack size, maximum
+//! depth and margin; with `--watch`, a snapshot is emitted every interval
+//! (tasks whose stacks cannot be read are omitted):
+//!
+//! ```console
+//! $ humility -d ./hubris.core.10 stackmargin --prometheus
+//! humility: attached to dump
+//! # HELP hubris_stack_size_bytes Stack size.
+//! # TYPE hubris_stack_size_bytes gauge
+//! hubris_stack_size_bytes{task="jefe"} 1024
+//! hubris_stack_size_bytes{task="rcc_driver"} 1024
+//! ...
+//! # HELP hubris_stack_margin_bytes Stack margin.
+//! # TYPE hubris_stack_margin_bytes gauge
+//! hubris_stack_margin_bytes{task="jefe"} 256
+//! hubris_stack_margin_bytes{task="rcc_driver"} 848
+//! ...
+//! ```
+//!
+//! When run against a dump that contains only some tasks' stacks, tasks whose
+//! stacks were not captured are displayed as `not captured` (and have
+//! `captured` set to `false` in the JSON output).
+//!
+//! If no stack contains any words of the fill pattern, the kernel presumably
+//! doesn't paint stacks (e.g., because they lie in a region that is
+//! initialized from flash), and depths cannot be determined; such stacks are
+//! displayed as `n/a (no fill pattern)` (and have `unpainted` set to `true`
+//! in the JSON output).  If other stacks are painted (or if its canary has
+//! been clobbered), a stack with no words of the fill pattern is instead
+//! taken to have overflowed.
+//!
+//! Margins can also be computed entirely offline from a raw snapshot of
+//! memory (e.g., of SRAM) along with the archive that was running, by
+//! specifying the image and the address at which it was taken with the
+//! global `--image` and `--image-addr` options; stacks that lie outside of
+//! the image are likewise displayed as `not captured`:
+//!
+//! ```console
+//! $ humility -a ./build-demo.zip --image sram.bin --image-addr 0x20000000 stackmargin
+//! humility: attached to memory image at 0x20000000
+//! ID TASK                STACKBASE  STACKSIZE   MAXDEPTH     MARGIN
+//!  0 jefe               0x20001000       1024        768        256
+//! ...
+//! ```
+//!
+//! To additionally display the total stack size, maximum depth and margin
+//! across all tasks (as well as the task with the least margin), use
+//! `--summary`:
+//!
+//! ```console
+//! $ humility -d ./hubris.core.10 stackmargin --summary
+//! humility: attached to dump
+//! ID TASK                STACKBASE  STACKSIZE   MAXDEPTH     MARGIN
+//!  0 jefe               0x20001000       1024        768        256
+//!  1 rcc_driver         0x20001400       1024        176        848
+//!  2 usart_driver       0x20001800       1024        216        808
+//!  3 user_leds          0x20001c00       1024        208        816
+//!  4 ping               0x20002000        512        224        288
+//!  5 pong               0x20002400       1024        208        816
+//!  6 idle               0x20002800        256        104        152
+//!    TOTAL                                5888       1904       3984
+//!    WORST              idle (152 bytes of margin)
+//! ```
+//!
+//! By default, tasks are displayed in task index order; to sort by a
+//! different field, use `--sort` (e.g., `--sort margin` will display the
+//! task with the least margin first).
+//!
+//! To fail if any task is running close to its stack limit, use
+//! `--threshold` to specify a minimum margin (in bytes); any task with a
+//! margin below the threshold will be marked with `!!` and the command will
+//! exit with an error after displaying all tasks.
+//!
+//! For use in scripts, `--exit-mask` (in conjunction with `--threshold`)
+//! will encode the tasks below the threshold in the exit status:  if the
+//! image has no more than 7 tasks, bit N + 1 of the exit status is set if
+//! and only if the task with ID N is below the threshold (e.g., an exit
+//! status of 40 indicates that tasks 2 and 4 are below the threshold).  If
+//! the image has more than 7 tasks (or if the kernel is below the
+//! threshold), the exit status is 1 on failure.  In all cases, an exit
+//! status of 0 indicates that all tasks are at or above the threshold.
+//!
+//! Some tasks deliberately run close to their stack limits; to display such
+//! tasks without failing on them, name them with `--ignore` (e.g.,
+//! `--threshold 256 --ignore idle jefe`).  Ignored tasks are neither marked
+//! nor included in the exit status.  A warning is displayed for any ignored
+//! task that doesn't exist.
+//!
+//! Having identified a task of concern, its stack (as captured when computing
+//! margins) can be displayed as a hexdump with `--dump-stack`, which takes
+//! the name or index of the task; to instead write the stack to a file, add
+//! `--dump-file`:
+//!
+//! ```console
+//! $ humility -d ./hubris.core.10 stackmargin --dump-stack 6 --dump-file s.bin 6
+//! humility: attached to dump
+//! ID TASK                STACKBASE  STACKSIZE   MAXDEPTH     MARGIN
+//!  6 idle               0x20002800        256        104        152
+//! humility: wrote 256 bytes of idle's stack to s.bin
+//! ```
+//!
+//! To see the peak stack usage over time, use `--compare` to specify
+//! additional dumps; for each task, the greatest maximum depth observed
+//! across the attached core and each of the dumps will be displayed, along
+//! with the dump that it was observed in.  Tasks are correlated by name, and
+//! all dumps must contain the same set of tasks:
+//!
+//! ```console
+//! $ humility -d ./hubris.core.10 stackmargin --compare ./hubris.core.11
+//! humility: attached to dump
+//! humility: attached to dump
+//! ID TASK                STACKSIZE   MAXDEPTH     MARGIN DUMP
+//!  0 jefe                     1024        768        256 ./hubris.core.10
+//!  1 rcc_driver               1024        176        848 ./hubris.core.10
+//!  2 usart_driver             1024        232        792 ./hubris.core.11
+//!  3 user_leds                1024        208        816 ./hubris.core.10
+//!  4 ping                      512        296        216 ./hubris.core.11
+//!  5 pong                     1024        208        816 ./hubris.core.10
+//!  6 idle                      256        104        152 ./hubris.core.10
+//! ```
+//!
+//! To see margins across a fleet, use `--fleet` to specify a directory of
+//! dumps; margins are computed for each dump in the directory (files that
+//! aren't dumps are skipped with a warning), and for each task, the minimum,
+//! median and maximum margin across the dumps will be displayed, along with
+//! the dump with the least margin.  The attached core is not included.
+//! Tasks are correlated by name; if the dumps are from different images,
+//! only tasks present in every dump are displayed:
+//!
+//! ```console
+//! $ humility -d fleet/a stackmargin --fleet fleet
+//! humility: attached to dump
+//! ID TASK               DUMPS        MIN     MEDIAN        MAX WORST
+//!  0 jefe                   3        256        256        272 fleet/a
+//!  1 rcc_driver             3        848        848        848 fleet/a
+//!  2 usart_driver           3        792        808        808 fleet/c
+//!  3 user_leds              3        816        816        816 fleet/a
+//!  4 ping                   3        216        240        288 fleet/b
+//!  5 pong                   3        816        816        816 fleet/a
+//!  6 idle                   3        152        152        152 fleet/a
+//! ```
+//!
+//! To gate changes on stack usage, use `--save-baseline` to write the
+//! current margins to a file, and then `--baseline` to later compare margins
+//! against it.  For each task, the current margin, the baseline margin and
+//! the change between them will be displayed; if any task's margin has
+//! decreased by more than `--tolerance` bytes (0 by default), the task is
+//! flagged with `!!` and the command fails.  Tasks are matched by both name
+//! and stack size, so a task that has been renamed or whose stack has been
+//! resized is displayed as `new` (and its former self as `removed`):
+//!
+//! ```console
+//! $ humility -d ./hubris.core.10 stackmargin --save-baseline margins.json
+//! humility: attached to dump
+//! humility: saved margins of 7 tasks to margins.json
+//! ...
+//! $ humility -d ./hubris.core.11 stackmargin --baseline margins.json
+//! humility: attached to dump
+//! ID TASK                STACKSIZE     MARGIN   BASELINE      DELTA
+//!  0 jefe                     1024        256        256         +0
+//!  1 rcc_driver               1024        848        848         +0
+//!  2 usart_driver             1024        792        808        -16 !!
+//!  3 user_leds                1024        816        816         +0
+//!  4 ping                      512        216        288        -72 !!
+//!  5 pong                     1024        816        816         +0
+//!  6 idle                      256        152        152         +0
+//! humility stackmargin failed: 2 tasks with margin decreased by more than 0 bytes
+//! ```
+//!
+//! If a task's stack cannot be mapped to exactly one region belonging to
+//! that task, `stackmargin` will fail; to instead display the region table
+//! (with the regions containing the task's initial stack pointer marked with
+//! `*` and the regions belonging to the task marked with `+`) and skip the
+//! task, use `--verbose`.
+//!
+//! To also display the margin of the kernel's stack (as determined from the
+//! archive rather than the task table), use `--kernel`; the kernel will be
+//! displayed as a pseudo-task named `kernel` with an ID of `-` (and with a
+//! `null` ID in the JSON output).
+//!
+//! When displaying to a terminal, margins are colored by severity: red for
+//! margins below 128 bytes (or below the threshold, if one is specified),
+//! yellow for margins below twice that, and green otherwise.  Color is not
+//! used if standard output is not a terminal or if `NO_COLOR` is set.
+//!
+//! If the kernel places a canary word at the base of each task's stack, use
+//! `--canary` to specify its value (e.g., `--canary 0xdeadbeef`); each task
+//! will be marked with `CANARY OK` or `CANARY CLOBBERED` depending on whether
+//! the canary is intact.  (A clobbered canary is a much stronger indication
+//! of stack overflow than a lack of margin.)
+//!
+//! To display each task's call chain (as unwound from its saved registers)
+//! below its margin, use `--backtrace`.  Note that this is the call chain at
+//! the time that the target was halted (or the dump taken) -- which is the
+//! call chain at the task's deepest point only if it happened to be there
+//! at that time:
+//!
+//! ```console
+//! $ humility -d ./hubris.core.10 stackmargin --backtrace ping
+//! humility: attached to dump
+//! ID TASK                STACKBASE  STACKSIZE   MAXDEPTH     MARGIN
+//!  4 ping               0x20002000        512        224        288
+//!    |
+//!    +--->  0x200021c8 0x08026e4e userlib::sys_send_stub
+//!           0x200021f0 0x08026a36 userlib::sys_send
+//!           0x20002200 0x0802605a task_ping::main
+//!           0x20002200 0x08026162 main
+//! ```
+//!
+//! When run repeatedly in an interactive session against a target that
+//! remains halted (or against a dump), the task table and each task's
+//! initial stack are read only once; they are read again after the target
+//! has been run.
+//!
+//! To see the memory region in which each task's stack was found (e.g., to
+//! understand how tasks' stacks are laid out in memory), use `--regions`;
+//! each task will be followed by the bounds and size of its region (and
+//! will have a `region` object with `base` and `size` members in the JSON
+//! output).
+//!
+//! If a task's stack size (as determined from its memory region) differs
+//! from the stack size declared for it in the app config, the task is
+//! marked with the declared size (e.g., `(declared 2048)`) and has
+//! `declared` set in the JSON output; this generally indicates that the
+//! task's region has been misidentified.
+//!
 //! Note that the margin is only valid for the task's lifetime -- and in
 //! particular, will not be correct if the task has restarted due to a
-//! stack overflow!
+//! stack overflow!  If the uninitialized pattern has been entirely consumed
+//! (that is, if even the word at the base of the stack has been overwritten),
+//! the task has very likely overflowed its stack; such tasks are marked with
+//! `OVERFLOW?` (and have `overflow` set in the JSON output).
+//!
+//! To see which tasks have restarted (and whose margins should therefore be
+//! discounted), use `--generation` to display each task's generation (which
+//! is incremented on every restart) in a `GEN` column.
+//!
+//! To compute margins from a clean start (e.g., after a known-good boot),
+//! use `--reset-first` to reset the target and let it run for the specified
+//! number of milliseconds before margins are computed (e.g.,
+//! `--reset-first 5000` to sample five seconds after reset).
+//!
+//! The idle task should use very little of its stack, so substantial depth
+//! there is a sign of stack misuse (e.g., by an interrupt handler).  To be
+//! warned when the idle task has used more than a given percentage of its
+//! stack, use `--idle-threshold` (e.g., `--idle-threshold 50`).
+//!
+//! To monitor margins on a running target (e.g., during a stress test), use
+//! `--watch`; every `--interval` milliseconds (1000 by default), the target
+//! will be briefly halted, margins recomputed, and the table redrawn, until
+//! interrupted with Ctrl-C.  The greatest depth seen for each task over the
+//! session is also displayed, in a `PEAK` column.
+//!
+//! To graph margins over time, `--emit` can be used with `--watch` to
+//! stream them to a TCP listener (e.g., `--emit 127.0.0.1:9000`) as well as
+//! displaying them.  Each task's margin is sent in each interval as a JSON
+//! object on its own line, with members as in the `--json` output plus a
+//! `time` member (in milliseconds since the Unix epoch).  Should the
+//! listener disconnect, sampling continues and the connection is retried
+//! in each subsequent interval.
 //!
 
-use anyhow::{bail, Result};
-use clap::{CommandFactory, Parser};
+use anyhow::{bail, Context, Result};
+use clap::{CommandFactory, Parser, ValueEnum};
+use colored::Colorize;
+use humility::core::{Core, CORE_HALT_TIMEOUT};
 use humility::hubris::*;
-use humility_cli::ExecutionContext;
-use humility_cmd::{Archive, Attach, Command, CommandKind, Validate};
-use std::convert::TryInto;
+use humility_cli::{ExecutionContext, Subcommand, TaskCache};
+use humility_cmd::{
+    Archive, Attach, Command, CommandKind, ExitStatus, Validate,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
 #[derive(Parser, Debug)]
 #[clap(name = "stackmargin", about = env!("CARGO_PKG_DESCRIPTION"))]
-struct StackmarginArgs {}
+struct StackmarginArgs {
+    /// generate JSON output
+    #[clap(short, long)]
+    json: bool,
 
-#[rustfmt::skip::macros(println, bail)]
-fn stackmargin(context: &mut ExecutionContext) -> Result<()> {
-    let core = &mut **context.core.as_mut().unwrap();
-    let hubris = context.archive.as_ref().unwrap();
+    /// generate YAML output
+    #[clap(long, conflicts_with = "json")]
+    yaml: bool,
+
+    /// generate comma-separated values (CSV) output
+    #[clap(long, conflicts_with_all = &["json", "yaml", "compare"])]
+    csv: bool,
+
+    /// generate output in the Prometheus text exposition format
+    #[clap(
+        long,
+        conflicts_with_all = &[
+            "json", "yaml", "csv", "compare", "baseline", "fleet", "summary",
+            "backtrace", "bars"
+        ]
+    )]
+    prometheus: bool,
+
+    /// sort tasks by the specified field (ascending)
+    #[clap(long, short, value_enum, default_value_t = Order::Id)]
+    sort: Order,
+
+    /// fail if any task has a margin below the specified number of bytes
+    #[clap(
+        long, short, value_name = "bytes",
+        parse(try_from_str = parse_int::parse)
+    )]
+    threshold: Option<u32>,
+
+    /// word used to detect untouched stack (by default, the pattern
+    /// declared by the archive or 0xbaddcafe)
+    #[clap(long, short, value_name = "word", parse(try_from_str = parse_word))]
+    pattern: Option<u32>,
+
+    /// check that the word at the base of each stack is the specified canary
+    #[clap(long, value_name = "word", parse(try_from_str = parse_word))]
+    canary: Option<u32>,
+
+    /// display totals and the task with the least margin after all tasks
+    #[clap(long, conflicts_with_all = &["json", "yaml", "csv", "compare"])]
+    summary: bool,
+
+    /// display the call chain of each task below its margin
+    #[clap(
+        long, short,
+        conflicts_with_all = &["json", "yaml", "csv", "compare"]
+    )]
+    backtrace: bool,
+
+    /// also display the margin as a percentage of the stack size
+    #[clap(long)]
+    percent: bool,
+
+    /// also display a bar showing the used proportion of each stack
+    #[clap(long, conflicts_with_all = &["json", "yaml", "csv", "compare"])]
+    bars: bool,
+
+    /// compare margins against a baseline written with --save-baseline,
+    /// failing if any task's margin has decreased by more than --tolerance
+    /// bytes
+    #[clap(
+        long, value_name = "file",
+        conflicts_with_all = &[
+            "compare", "watch", "save-baseline", "csv", "backtrace",
+            "threshold"
+        ]
+    )]
+    baseline: Option<String>,
+
+    /// also write the margins to the specified file, for later use with
+    /// --baseline
+    #[clap(
+        long, value_name = "file", conflicts_with_all = &["compare", "watch"]
+    )]
+    save_baseline: Option<String>,
+
+    /// with --baseline, the number of bytes by which a task's margin may
+    /// decrease before it is flagged (0 by default)
+    #[clap(
+        long, value_name = "bytes", requires = "baseline",
+        parse(try_from_str = parse_int::parse)
+    )]
+    tolerance: Option<u32>,
+
+    /// after computing margins, display the captured stack of the specified
+    /// task (by name or by index) as a hexdump
+    #[clap(
+        long, value_name = "task",
+        conflicts_with_all = &["compare", "watch", "baseline"]
+    )]
+    dump_stack: Option<String>,
+
+    /// with --dump-stack, write the stack to the specified file rather than
+    /// displaying it
+    #[clap(long, value_name = "file", requires = "dump-stack")]
+    dump_file: Option<String>,
+
+    /// also display the address of the deepest word written in each stack
+    #[clap(long, conflicts_with = "compare")]
+    show_addr: bool,
+
+    /// display, for each task, the peak stack depth across the attached
+    /// core and the specified dumps
+    #[clap(
+        long, value_name = "dump", multiple_values = true,
+        conflicts_with_all = &["sort", "threshold", "percent"]
+    )]
+    compare: Vec<String>,
+
+    /// display, for each task, the minimum, median and maximum margin across
+    /// all dumps in the specified directory
+    #[clap(
+        long, value_name = "dir",
+        conflicts_with_all = &[
+            "compare", "sort", "threshold", "percent", "watch", "baseline",
+            "save-baseline", "dump-stack", "csv"
+        ]
+    )]
+    fleet: Option<String>,
+
+    /// also display the memory region containing each task's stack
+    #[clap(long, short, conflicts_with_all = &["csv", "compare"])]
+    regions: bool,
+
+    /// also display each task's generation (that is, its restart count)
+    #[clap(long, short, conflicts_with = "compare")]
+    generation: bool,
+
+    /// repeatedly recompute and redisplay margins until interrupted
+    #[clap(
+        long, short,
+        conflicts_with_all = &[
+            "json", "yaml", "csv", "compare", "backtrace", "exit-mask"
+        ]
+    )]
+    watch: bool,
+
+    /// interval between recomputations when watching, in milliseconds
+    #[clap(
+        long, short, value_name = "ms", default_value_t = 1000,
+        requires = "watch", parse(try_from_str = parse_int::parse)
+    )]
+    interval: u64,
+
+    /// with --watch, also stream each task's margin as JSON to the specified
+    /// TCP address
+    #[clap(long, value_name = "addr:port", requires = "watch")]
+    emit: Option<String>,
+
+    /// with --threshold, exit with a bitmask of the IDs of the tasks below
+    /// the threshold
+    #[clap(long, requires = "threshold")]
+    exit_mask: bool,
+
+    /// with --threshold, tasks (by name) that should not cause failure when
+    /// below the threshold
+    #[clap(
+        long,
+        value_name = "task",
+        multiple_values = true,
+        requires = "threshold"
+    )]
+    ignore: Vec<String>,
+
+    /// warn if the idle task has used more than the specified percentage of
+    /// its stack
+    #[clap(
+        long, value_name = "percent",
+        parse(try_from_str = parse_int::parse)
+    )]
+    idle_threshold: Option<u32>,
+
+    /// reset the target and let it run for the specified number of
+    /// milliseconds before computing margins
+    #[clap(
+        long, value_name = "ms", conflicts_with = "compare",
+        parse(try_from_str = parse_int::parse)
+    )]
+    reset_first: Option<u64>,
+
+    /// on a task/region mismatch, display the region table rather than
+    /// failing
+    #[clap(long, short)]
+    verbose: bool,
 
-    let regions = hubris.regions(core)?;
+    /// over the network, attempt to read the supervisor's stack rather than
+    /// skipping it
+    #[clap(long)]
+    force_supervisor: bool,
+
+    /// also display the margin of the kernel's stack
+    #[clap(long, short)]
+    kernel: bool,
+
+    /// single task to display, by name or by index
+    task: Option<String>,
+}
+
+fn parse_word(s: &str) -> Result<u32> {
+    parse_int::parse::<u32>(s).map_err(|_| {
+        anyhow::anyhow!("invalid word \"{s}\": expected a 32-bit value")
+    })
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+#[clap(rename_all = "kebab-case")]
+enum Order {
+    /// Sort by task index (the default).
+    Id,
+    /// Sort by stack size.
+    Stacksize,
+    /// Sort by maximum stack depth.
+    Maxdepth,
+    /// Sort by stack margin.
+    Margin,
+}
+
+#[derive(Debug, Serialize)]
+struct StackmarginRow {
+    id: Option<u32>,
+    task: String,
+    stackbase: Option<u32>,
+    stacksize: Option<u32>,
+    maxdepth: Option<u32>,
+    margin: Option<u32>,
+    overflow: bool,
+    captured: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    canary: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    declared: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<StackmarginRegion>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generation: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    peak: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deepest: Option<u32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    unpainted: bool,
+    #[serde(skip)]
+    stack: Option<Vec<u8>>,
+}
+
+#[derive(Copy, Clone, Debug, Serialize)]
+struct StackmarginRegion {
+    base: u32,
+    size: u32,
+}
+
+///
+/// Resolves a task specified either by name or by index into a task index.
+///
+fn lookup_task(hubris: &HubrisArchive, task: &str, count: u32) -> Result<u32> {
+    if let Some(HubrisTask::Task(ndx)) = hubris.lookup_task(task) {
+        return Ok(*ndx);
+    }
+
+    if let Ok(ndx) = parse_int::parse::<u32>(task) {
+        if ndx < count {
+            return Ok(ndx);
+        }
+    }
+
+    let names = (0..count)
+        .filter_map(|i| hubris.lookup_module(HubrisTask::Task(i)).ok())
+        .map(|m| m.name.as_str())
+        .collect::<Vec<_>>();
+
+    bail!("unknown task \"{task}\"; valid tasks: {}", names.join(", "));
+}
+
+///
+/// Computes the stack margin of each task (or of the single task specified
+/// on the command line).
+///
+#[rustfmt::skip::macros(bail)]
+fn stackmargin_rows(
+    hubris: &HubrisArchive,
+    core: &mut dyn Core,
+    cache: &mut Option<TaskCache>,
+    subargs: &StackmarginArgs,
+) -> Result<Vec<StackmarginRow>> {
+    //
+    // If the core hasn't run since we last determined the task table and
+    // each task's initial stack (e.g., in an earlier command in the same
+    // session), we reuse what we determined then.
+    //
+    let cached = TaskCache::valid(cache, core);
+
+    let (base, size) = match &cached {
+        Some(cached) => cached.task_table,
+        None => hubris.task_table(core)?,
+    };
 
-    let (base, size) = hubris.task_table(core)?;
-    let task = hubris.lookup_struct_byname("Task")?;
-    let taskdesc = hubris.lookup_struct_byname("TaskDesc")?;
     let task_dump = hubris.task_dump();
 
-    let mut taskblock: Vec<u8> = vec![];
-    taskblock.resize_with(task.size * size as usize, Default::default);
+    //
+    // Over the network, we generally can't read the supervisor's memory --
+    // but some configurations can, so we allow the read to be attempted
+    // (falling back to skipping the supervisor if it fails).
+    //
+    let supervisor = !core.is_net() || subargs.force_supervisor;
 
-    if let Some(HubrisTask::Task(i)) = task_dump {
-        let offs = i as usize * task.size;
-        let addr = base + offs as u32;
-        core.read_8(addr, &mut taskblock[offs..offs + task.size])?;
-    } else if core.is_net() {
+    if !supervisor {
         humility::msg!(
             "skipping supervisor because we are reading over the network"
         );
-        core.read_8(base + task.size as u32, &mut taskblock[task.size..])?;
-    } else {
-        core.read_8(base, &mut taskblock)?;
     }
 
-    let descriptor = task.lookup_member("descriptor")?.offset as u32;
-    let initial_stack = taskdesc.lookup_member("initial_stack")?.offset as u32;
-
-    println!("{:2} {:18} {:>10} {:>10} {:>10} {:>10}",
-        "ID", "TASK", "STACKBASE", "STACKSIZE", "MAXDEPTH", "MARGIN");
+    //
+    // Stacks are only ever in RAM, so we only look for them there.
+    //
+    let ram = hubris.regions_by_kind(core, HubrisRegionKind::Ram)?;
 
-    let taskblock32 =
-        |o| u32::from_le_bytes(taskblock[o..o + 4].try_into().unwrap());
+    let find = |addr| match HubrisArchive::region_containing(&ram, addr) {
+        Some(region) => Ok(region),
+        None => bail!(format!("could not find region for address {:x}", addr)),
+    };
 
-    let find = |addr| {
-        for (_, region) in regions.iter() {
-            if addr > region.base && addr <= region.base + region.size {
-                return Ok(region);
-            }
-        }
+    let only = match &subargs.task {
+        Some(name) => Some(lookup_task(hubris, name, size)?),
+        None => None,
+    };
 
-        bail!(format!("could not find region for address {:x}", addr));
+    let dump = match &subargs.dump_stack {
+        Some(name) => Some(lookup_task(hubris, name, size)?),
+        None => None,
     };
 
+    //
+    // First, determine where each task's stack lives.
+    //
+    let kernel = "kernel".to_string();
+    let mut stacks = vec![];
+    let mut initial_stacks = BTreeMap::new();
+    let mut stack_regions = BTreeMap::new();
+
     for i in 0..size {
         if let Some(HubrisTask::Task(ndx)) = task_dump {
             if ndx != i {
@@ -93,48 +725,1452 @@ fn stackmargin(context: &mut ExecutionContext) -> Result<()> {
         }
 
         let module = hubris.lookup_module(HubrisTask::Task(i))?;
+        let skip = only.map_or(false, |ndx| ndx != i);
 
-        if core.is_net() && i == 0 {
-            println!(
-                "{:2} {:18} unknown (cannot read supervisor memory remotely)",
-                i, module.name
-            );
+        if !supervisor && i == 0 {
+            if !skip {
+                stacks.push((Some(i), &module.name, None));
+            }
             continue;
         }
 
-        let offs = i as usize * task.size;
-        let daddr = taskblock32(offs + descriptor as usize);
-        let initial = core.read_word_32(daddr + initial_stack)?;
+        //
+        // If we have a cached initial stack for this task, we use it;
+        // otherwise, we read it through the task table.
+        //
+        let initial =
+            match cached.as_ref().and_then(|c| c.initial_stacks.get(&i)) {
+                Some(&initial) => Ok(initial),
+                None => hubris.task_initial_stack(core, HubrisTask::Task(i)),
+            };
 
-        let region = find(initial)?;
+        let initial = match initial {
+            Ok(initial) => initial,
+            Err(_) if core.is_net() && i == 0 => {
+                humility::msg!("could not read supervisor descriptor");
 
-        if region.tasks.len() != 1 || region.tasks[0] != module.task {
-            bail!(format!(
-                "mismatched task on 0x{:x}: expected {:?}, found {:?}",
-                initial, module.task, region.tasks)
-            )
+                if !skip {
+                    stacks.push((Some(i), &module.name, None));
+                }
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        //
+        // We note every task's initial stack (even those that we're not
+        // displaying) so that a subsequent command can use all of them.
+        //
+        initial_stacks.insert(i, initial);
+
+        if skip {
+            continue;
         }
 
+        //
+        // The initial stack pointer is one past the end of the stack, so the
+        // stack is in the region that contains the word below it -- which
+        // must belong to this task alone.
+        //
+        let owner = initial
+            .checked_sub(1)
+            .and_then(|addr| HubrisArchive::region_owner(&ram, addr));
+
+        let region = match owner {
+            Some((region, task)) if task == module.task => region,
+            _ if subargs.verbose => {
+                humility::warn!(
+                    "{}: cannot determine stack region for initial stack \
+                    0x{:x}: {}",
+                    module.name,
+                    initial,
+                    match find(initial) {
+                        Ok(region) => format!("found {:?}", region.tasks),
+                        Err(err) => err.to_string(),
+                    }
+                );
+
+                let regions = hubris.regions(core)?;
+                stackmargin_regions(hubris, &regions, initial, module.task)?;
+                continue;
+            }
+            _ => {
+                let region = find(initial)?;
+
+                bail!(format!(
+                    "mismatched task on 0x{:x}: expected {:?}, found {:?} \
+                    (use --verbose to display regions)",
+                    initial, module.task, region.tasks)
+                )
+            }
+        };
+
         let size = (initial - region.base) as usize;
-        let mut stack: Vec<u8> = vec![];
-        stack.resize_with(size, Default::default);
-        core.read_8(region.base, stack.as_mut_slice())?;
+        stacks.push((Some(i), &module.name, Some((region.base, size))));
+
+        if subargs.regions {
+            let (base, size) = (region.base, region.size);
+            stack_regions.insert(i, StackmarginRegion { base, size });
+        }
+    }
+
+    if let Some(epoch) = core.halted_epoch() {
+        *cache =
+            Some(TaskCache { epoch, task_table: (base, size), initial_stacks });
+    }
+
+    //
+    // The kernel's stack isn't in the task table; we get its bounds from the
+    // archive instead.
+    //
+    if subargs.kernel {
+        match hubris.kernel_stack() {
+            Some(_) if core.is_net() => {
+                humility::msg!(
+                    "skipping kernel because we are reading over the network"
+                );
+                stacks.push((None, &kernel, None));
+            }
+            Some(_) if task_dump.is_some() => {
+                humility::msg!(
+                    "skipping kernel because dump contains only a single task"
+                );
+            }
+            Some((base, size)) => {
+                stacks.push((None, &kernel, Some((base, size as usize))));
+            }
+            None => bail!("kernel stack not found in archive"),
+        }
+    }
+
+    //
+    // If we're attempting the supervisor over the network, probe its stack
+    // first: a failure there shouldn't fail the batch for every other task.
+    //
+    if core.is_net() && supervisor {
+        if let Some(s) = stacks.iter_mut().find(|s| s.0 == Some(0)) {
+            if let Some((base, _)) = s.2 {
+                if core.read_word_32(base).is_err() {
+                    humility::msg!("could not read supervisor stack");
+                    s.2 = None;
+                }
+            }
+        }
+    }
+
+    //
+    // Now read the stacks themselves -- in a single batch, which allows the
+    // core to coalesce reads (which makes a substantial difference when
+    // reading over the network).
+    //
+    let requests = stacks.iter().filter_map(|s| s.2).collect::<Vec<_>>();
+
+    //
+    // A dump need not contain every task's stack; if we're reading from a
+    // dump, we read each stack individually and note those that weren't
+    // captured rather than failing outright.
+    //
+    let bufs = if core.is_dump() {
+        requests
+            .iter()
+            .map(|&(addr, len)| {
+                let mut buf = vec![0; len];
+                core.read_8(addr, &mut buf).ok().map(|_| buf)
+            })
+            .collect::<Vec<_>>()
+    } else {
+        core.read_batch(&requests)?.into_iter().map(Some).collect()
+    };
+
+    let mut bufs = bufs.into_iter();
+    let mut rows = vec![];
+    let pattern = subargs.pattern.unwrap_or_else(|| hubris.stack_fill());
+
+    //
+    // The stack size that we derive from the region should match the size
+    // declared in the app config; if it doesn't, the region mapping is likely
+    // off, so we note the declared size.
+    //
+    let declared = |i: Option<u32>, name: &String, size: usize| {
+        i.and(hubris.manifest.task_stacksize.get(name))
+            .copied()
+            .filter(|&declared| declared as usize != size)
+    };
+
+    for (i, name, stack) in stacks {
+        let Some((base, size)) = stack else {
+            rows.push(StackmarginRow {
+                id: i,
+                task: name.clone(),
+                stackbase: None,
+                stacksize: None,
+                maxdepth: None,
+                margin: None,
+                overflow: false,
+                captured: false,
+                canary: None,
+                declared: None,
+                region: None,
+                generation: None,
+                peak: None,
+                deepest: None,
+                unpainted: false,
+                stack: None,
+            });
+            continue;
+        };
+
+        let Some(stack) = bufs.next().unwrap() else {
+            rows.push(StackmarginRow {
+                id: i,
+                task: name.clone(),
+                stackbase: Some(base),
+                stacksize: Some(size as u32),
+                maxdepth: None,
+                margin: None,
+                overflow: false,
+                captured: false,
+                canary: None,
+                declared: declared(i, name, size),
+                region: i.and_then(|i| stack_regions.get(&i).copied()),
+                generation: None,
+                peak: None,
+                deepest: None,
+                unpainted: false,
+                stack: None,
+            });
+            continue;
+        };
+
+        //
+        // If the kernel places a canary at the base of the stack, it isn't
+        // part of the pattern:  check it, and start our walk after it.
+        //
+        let canary = match subargs.canary {
+            Some(canary) => Some(hubris.read_u32(&stack)? == canary),
+            None => None,
+        };
 
-        let mut o = 0;
+        let start = if canary.is_some() { 4 } else { 0 };
+        let mut o = start;
+
+        //
+        // Note if there isn't a single word of our pattern anywhere in the
+        // stack; we determine what that means once we have seen every stack.
+        //
+        let mut painted = false;
+
+        for word in stack[start..].chunks_exact(4) {
+            if hubris.read_u32(word)? == pattern {
+                painted = true;
+                break;
+            }
+        }
 
+        //
+        // Stacks grow down, so we walk up from the base of the stack:  the
+        // first word that isn't our pattern is the deepest that the stack
+        // has ever reached -- even if shallower words have since been left
+        // untouched (or have happened to be written with the pattern).
+        //
         let depth = loop {
-            let c = u32::from_le_bytes(stack[o..o + 4].try_into().unwrap());
+            let c = hubris.read_u32(&stack[o..])?;
 
-            if c != 0xbaddcafe || o + 4 >= size {
+            if c != pattern || o + 4 >= size {
                 break size - o;
             }
 
             o += 4;
         };
 
-        println!("{:2} {:18} 0x{:<8x} {:10} {:10} {:10}",
-            i, module.name, region.base,
-            size, depth, size - depth);
+        //
+        // If the very first word of the stack isn't our pattern, the stack
+        // has been entirely consumed:  the task has almost certainly
+        // overflowed (and our depth is a lower bound at best).
+        //
+        let overflow = depth == size - start;
+
+        rows.push(StackmarginRow {
+            id: i,
+            task: name.clone(),
+            stackbase: Some(base),
+            stacksize: Some(size as u32),
+            maxdepth: Some(depth as u32),
+            margin: Some((size - depth) as u32),
+            overflow,
+            captured: true,
+            canary,
+            declared: declared(i, name, size),
+            region: i.and_then(|i| stack_regions.get(&i).copied()),
+            generation: match (subargs.generation, i) {
+                (true, Some(i)) => {
+                    hubris.task_generation(core, HubrisTask::Task(i)).ok()
+                }
+                _ => None,
+            },
+            peak: None,
+            deepest: if subargs.show_addr {
+                Some(base + o as u32)
+            } else {
+                None
+            },
+            unpainted: !painted,
+            stack: if dump.is_some() && i == dump { Some(stack) } else { None },
+        });
+    }
+
+    //
+    // A stack without a single word of our pattern has either overflowed so
+    // badly as to consume all of it, or was never painted at all (e.g.,
+    // because it lies in a region that is initialized from flash).  If any
+    // other stack has been painted, the kernel paints stacks, and we take
+    // it to be the former -- as we do if its canary has been clobbered.
+    // Otherwise, the depth that we computed is meaningless.
+    //
+    let paints = rows.iter().any(|row| row.captured && !row.unpainted);
+
+    for row in rows.iter_mut() {
+        if row.unpainted && (paints || row.canary == Some(false)) {
+            row.unpainted = false;
+        }
+
+        if row.unpainted {
+            row.maxdepth = None;
+            row.margin = None;
+            row.deepest = None;
+            row.overflow = false;
+        }
+
+        if row.overflow {
+            humility::warn!(
+                "{}: stack pattern entirely consumed; possible overflow",
+                row.task
+            );
+        }
+    }
+
+    Ok(rows)
+}
+
+///
+/// Determines whether we should draw bars with Unicode block elements:  we
+/// fall back to ASCII if the locale isn't UTF-8 or if NO_COLOR is set (which
+/// suggests a terminal that wants plain output).
+///
+fn stackmargin_unicode() -> bool {
+    if std::env::var_os("NO_COLOR").is_some() {
+        return false;
+    }
+
+    ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|v| std::env::var(v).ok().filter(|s| !s.is_empty()))
+        .map_or(false, |locale| {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        })
+}
+
+///
+/// Renders the used proportion of a stack as a bar.  We round up, so that a
+/// stack that has been used at all has at least one filled cell.
+///
+fn stackmargin_bar(depth: u32, size: u32, unicode: bool) -> String {
+    const WIDTH: u64 = 10;
+
+    let used = match size {
+        0 => 0,
+        _ => {
+            ((depth as u64 * WIDTH + size as u64 - 1) / size as u64).min(WIDTH)
+        }
+    };
+
+    let (full, empty) =
+        if unicode { ("\u{2588}", "\u{2591}") } else { ("#", "-") };
+
+    format!(
+        "[{}{}]",
+        full.repeat(used as usize),
+        empty.repeat((WIDTH - used) as usize)
+    )
+}
+
+///
+/// Displays the region table to help diagnose a task whose stack cannot be
+/// mapped to a region.  Regions that contain the task's initial stack are
+/// marked with `*`; regions that belong to the task are marked with `+`.
+///
+fn stackmargin_regions(
+    hubris: &HubrisArchive,
+    regions: &BTreeMap<u32, HubrisRegion>,
+    initial: u32,
+    task: HubrisTask,
+) -> Result<()> {
+    eprintln!(
+        "  {:10}   {:10} {:>10} {:6} TASKS",
+        "LOW", "HIGH", "SIZE", "ATTR"
+    );
+
+    for (_, region) in regions.iter() {
+        let mut names = vec![];
+
+        for t in &region.tasks {
+            names.push(hubris.lookup_module(*t)?.name.clone());
+        }
+
+        let contains =
+            initial > region.base && initial <= region.base + region.size;
+
+        eprintln!(
+            "{}{}0x{:08x} - 0x{:08x} {:>10} {}{}{}{}{}{} {}",
+            if contains { "*" } else { " " },
+            if region.tasks.contains(&task) { "+" } else { " " },
+            region.base,
+            region.base + region.size - 1,
+            region.size,
+            if region.attr.read { "r" } else { "-" },
+            if region.attr.write { "w" } else { "-" },
+            if region.attr.execute { "x" } else { "-" },
+            if region.attr.device { "d" } else { "-" },
+            if region.attr.dma { "m" } else { "-" },
+            if region.attr.external { "e" } else { "-" },
+            names.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct StackmarginPeak {
+    id: Option<u32>,
+    task: String,
+    stacksize: Option<u32>,
+    maxdepth: Option<u32>,
+    margin: Option<u32>,
+    dump: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct StackmarginFleet {
+    id: Option<u32>,
+    task: String,
+    dumps: usize,
+    min: Option<u32>,
+    median: Option<u32>,
+    max: Option<u32>,
+    worst: Option<String>,
+}
+
+///
+/// Computes margins for each dump in the specified directory, displaying the
+/// minimum, median and maximum margin of each task across them along with
+/// the dump in which the margin was least.  Tasks are correlated by name;
+/// only tasks present in every dump are displayed.
+///
+#[rustfmt::skip::macros(println)]
+fn stackmargin_fleet(dir: &str, subargs: &StackmarginArgs) -> Result<()> {
+    let mut paths = std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read directory \"{dir}\""))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+
+    paths.retain(|p| p.is_file());
+    paths.sort();
+
+    let mut results = vec![];
+
+    for path in &paths {
+        let dump = path.display().to_string();
+        let mut archive = HubrisArchive::new()?;
+
+        let loaded = archive.load_dump(&dump, HubrisArchiveDoneness::Cook);
+
+        if let Err(err) = loaded {
+            humility::warn!("skipping \"{dump}\": {err}");
+            continue;
+        }
+
+        if !archive.loaded() {
+            humility::warn!("skipping \"{dump}\": no Hubris archive");
+            continue;
+        }
+
+        let mut core = humility::core::attach_dump(&dump, &archive)?;
+        let mut cache = None;
+        let rows = stackmargin_rows(&archive, &mut *core, &mut cache, subargs)
+            .with_context(|| format!("failed on dump \"{dump}\""))?;
+
+        results.push((dump, rows));
+    }
+
+    let Some((_, first)) = results.first() else {
+        bail!("no dumps found in \"{dir}\"");
+    };
+
+    //
+    // Dumps from different images may have different tasks; we only consider
+    // the tasks that all of them have in common.
+    //
+    let common = |task: &String| {
+        results.iter().all(|(_, rows)| rows.iter().any(|r| r.task == *task))
+    };
+
+    let omitted = results
+        .iter()
+        .flat_map(|(_, rows)| rows.iter().map(|r| &r.task))
+        .filter(|task| !common(task))
+        .collect::<BTreeSet<_>>();
+
+    if !omitted.is_empty() {
+        humility::warn!(
+            "omitting tasks not present in every dump: {}",
+            omitted.into_iter().cloned().collect::<Vec<_>>().join(", ")
+        );
+    }
+
+    let mut fleet = vec![];
+
+    for row in first.iter().filter(|r| common(&r.task)) {
+        let mut margins = results
+            .iter()
+            .filter_map(|(dump, rows)| {
+                let r = rows.iter().find(|r| r.task == row.task).unwrap();
+                r.margin.map(|margin| (margin, dump))
+            })
+            .collect::<Vec<_>>();
+
+        margins.sort();
+
+        let n = margins.len();
+
+        fleet.push(StackmarginFleet {
+            id: row.id,
+            task: row.task.clone(),
+            dumps: n,
+            min: margins.first().map(|&(m, _)| m),
+            median: match n {
+                0 => None,
+                _ if n % 2 == 1 => Some(margins[n / 2].0),
+                _ => Some((margins[n / 2 - 1].0 + margins[n / 2].0) / 2),
+            },
+            max: margins.last().map(|&(m, _)| m),
+            worst: margins.first().map(|&(_, dump)| dump.clone()),
+        });
+    }
+
+    if subargs.json {
+        println!("{}", serde_json::to_string(&fleet)?);
+        return Ok(());
+    }
+
+    if subargs.yaml {
+        print!("{}", serde_yaml::to_string(&fleet)?);
+        return Ok(());
+    }
+
+    let id = |id: Option<u32>| match id {
+        Some(id) => id.to_string(),
+        None => "-".to_string(),
+    };
+
+    println!("{:2} {:18} {:>5} {:>10} {:>10} {:>10} {}",
+        "ID", "TASK", "DUMPS", "MIN", "MEDIAN", "MAX", "WORST");
+
+    for f in &fleet {
+        match (f.min, f.median, f.max, &f.worst) {
+            (Some(min), Some(median), Some(max), Some(worst)) => {
+                println!("{:>2} {:18} {:5} {:10} {:10} {:10} {}",
+                    id(f.id), f.task, f.dumps, min, median, max, worst);
+            }
+            _ => {
+                println!("{:>2} {:18} unknown", id(f.id), f.task);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// A task's margin as recorded in a baseline file.  Tasks are identified by
+/// both name and stack size, so that a task that has been renamed or resized
+/// is reported as new rather than compared against a stack it no longer has.
+///
+#[derive(Debug, Serialize, Deserialize)]
+struct StackmarginBaseline {
+    task: String,
+    stacksize: u32,
+    maxdepth: u32,
+    margin: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum StackmarginStatus {
+    Ok,
+    Regressed,
+    New,
+    Removed,
+    NotCaptured,
+}
+
+#[derive(Debug, Serialize)]
+struct StackmarginDelta {
+    id: Option<u32>,
+    task: String,
+    stacksize: u32,
+    margin: Option<u32>,
+    baseline: Option<u32>,
+    delta: Option<i64>,
+    status: StackmarginStatus,
+}
+
+///
+/// Writes the margins of all captured stacks to the specified file, to be
+/// later compared against with `--baseline`.
+///
+fn stackmargin_save_baseline(
+    rows: &[StackmarginRow],
+    path: &str,
+) -> Result<()> {
+    let baseline = rows
+        .iter()
+        .filter_map(|row| match (row.stacksize, row.maxdepth, row.margin) {
+            (Some(stacksize), Some(maxdepth), Some(margin)) => {
+                Some(StackmarginBaseline {
+                    task: row.task.clone(),
+                    stacksize,
+                    maxdepth,
+                    margin,
+                })
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut file = std::fs::File::create(path)
+        .with_context(|| format!("failed to create \"{path}\""))?;
+
+    serde_json::to_writer_pretty(&mut file, &baseline)?;
+    writeln!(file)?;
+
+    humility::msg!("saved margins of {} tasks to {path}", baseline.len());
+
+    Ok(())
+}
+
+///
+/// Displays the captured stack of the task specified with `--dump-stack` as a
+/// hexdump, or writes it to the file specified with `--dump-file`.
+///
+fn stackmargin_dump_stack(
+    rows: &[StackmarginRow],
+    task: &str,
+    file: Option<&String>,
+) -> Result<()> {
+    let Some(row) = rows.iter().find(|r| r.stack.is_some()) else {
+        bail!("stack of task \"{task}\" was not captured");
+    };
+
+    let stack = row.stack.as_ref().unwrap();
+
+    match file {
+        Some(path) => {
+            std::fs::write(path, stack)
+                .with_context(|| format!("failed to write \"{path}\""))?;
+
+            humility::msg!(
+                "wrote {} bytes of {}'s stack to {path}",
+                stack.len(),
+                row.task
+            );
+        }
+        None => {
+            println!();
+            humility_cmd::Dumper::new().dump(stack, row.stackbase.unwrap());
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// Compares margins against a baseline written with `--save-baseline`,
+/// displaying the change in each task's margin and failing if any has
+/// decreased by more than the tolerance.
+///
+#[rustfmt::skip::macros(println)]
+fn stackmargin_baseline(
+    rows: &[StackmarginRow],
+    path: &str,
+    subargs: &StackmarginArgs,
+) -> Result<()> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read baseline \"{path}\""))?;
+
+    let baseline: Vec<StackmarginBaseline> = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse baseline \"{path}\""))?;
+
+    let tolerance = subargs.tolerance.unwrap_or(0);
+    let mut matched = BTreeSet::new();
+    let mut deltas = vec![];
+
+    for row in rows {
+        let Some(stacksize) = row.stacksize else {
+            continue;
+        };
+
+        let found = baseline
+            .iter()
+            .position(|b| b.task == row.task && b.stacksize == stacksize);
+
+        if let Some(ndx) = found {
+            matched.insert(ndx);
+        }
+
+        let base = found.map(|ndx| baseline[ndx].margin);
+
+        let (delta, status) = match (row.margin, base) {
+            (Some(margin), Some(base)) => {
+                let delta = margin as i64 - base as i64;
+
+                if -delta > tolerance as i64 {
+                    (Some(delta), StackmarginStatus::Regressed)
+                } else {
+                    (Some(delta), StackmarginStatus::Ok)
+                }
+            }
+            (Some(_), None) => (None, StackmarginStatus::New),
+            (None, _) => (None, StackmarginStatus::NotCaptured),
+        };
+
+        deltas.push(StackmarginDelta {
+            id: row.id,
+            task: row.task.clone(),
+            stacksize,
+            margin: row.margin,
+            baseline: base,
+            delta,
+            status,
+        });
+    }
+
+    for (ndx, b) in baseline.iter().enumerate() {
+        if !matched.contains(&ndx) {
+            deltas.push(StackmarginDelta {
+                id: None,
+                task: b.task.clone(),
+                stacksize: b.stacksize,
+                margin: None,
+                baseline: Some(b.margin),
+                delta: None,
+                status: StackmarginStatus::Removed,
+            });
+        }
+    }
+
+    if subargs.json {
+        println!("{}", serde_json::to_string(&deltas)?);
+    } else if subargs.yaml {
+        print!("{}", serde_yaml::to_string(&deltas)?);
+    } else {
+        let field = |v: Option<u32>| match v {
+            Some(v) => v.to_string(),
+            None => "-".to_string(),
+        };
+
+        println!("{:2} {:18} {:>10} {:>10} {:>10} {:>10}",
+            "ID", "TASK", "STACKSIZE", "MARGIN", "BASELINE", "DELTA");
+
+        for d in &deltas {
+            println!("{:>2} {:18} {:10} {:>10} {:>10} {:>10}{}",
+                field(d.id), d.task, d.stacksize, field(d.margin),
+                field(d.baseline),
+                d.delta.map_or("-".to_string(), |d| format!("{d:+}")),
+                match d.status {
+                    StackmarginStatus::Ok => "",
+                    StackmarginStatus::Regressed => " !!",
+                    StackmarginStatus::New => " new",
+                    StackmarginStatus::Removed => " removed",
+                    StackmarginStatus::NotCaptured => " not captured",
+                });
+        }
+    }
+
+    let regressed = deltas
+        .iter()
+        .filter(|d| d.status == StackmarginStatus::Regressed)
+        .count();
+
+    if regressed > 0 {
+        bail!(
+            "{} task{} with margin decreased by more than {} bytes",
+            regressed,
+            if regressed == 1 { "" } else { "s" },
+            tolerance
+        );
+    }
+
+    Ok(())
+}
+
+///
+/// Displays, for each task, the maximum stack depth observed across the
+/// attached core and each of the dumps specified via `--compare`.  Tasks
+/// are correlated by name; all dumps must contain the same set of tasks.
+///
+#[rustfmt::skip::macros(println, bail)]
+fn stackmargin_compare(
+    hubris: &HubrisArchive,
+    core: &mut dyn Core,
+    cache: &mut Option<TaskCache>,
+    name: String,
+    subargs: &StackmarginArgs,
+) -> Result<()> {
+    let rows = stackmargin_rows(hubris, core, cache, subargs)?;
+    let mut results = vec![(name, rows)];
+
+    for dump in &subargs.compare {
+        let mut archive = HubrisArchive::new()?;
+
+        archive
+            .load_dump(dump, HubrisArchiveDoneness::Cook)
+            .with_context(|| format!("failed to load dump \"{dump}\""))?;
+
+        if !archive.loaded() {
+            bail!("dump \"{dump}\" does not contain a Hubris archive");
+        }
+
+        let mut core = humility::core::attach_dump(dump, &archive)?;
+        let rows = stackmargin_rows(&archive, &mut *core, &mut None, subargs)?;
+        results.push((dump.clone(), rows));
+    }
+
+    let tasks = |rows: &[StackmarginRow]| {
+        rows.iter().map(|r| r.task.as_str()).collect::<BTreeSet<_>>()
+    };
+
+    let (first, rows) = &results[0];
+    let expected = tasks(rows);
+
+    for (dump, rows) in &results[1..] {
+        if tasks(rows) != expected {
+            bail!("tasks in \"{dump}\" do not match tasks in \"{first}\"");
+        }
+    }
+
+    let mut peaks = vec![];
+
+    for row in rows {
+        let mut peak: Option<(&StackmarginRow, &String)> = None;
+
+        for (dump, rows) in &results {
+            let r = rows.iter().find(|r| r.task == row.task).unwrap();
+
+            if let Some(depth) = r.maxdepth {
+                if peak.map_or(true, |(p, _)| Some(depth) > p.maxdepth) {
+                    peak = Some((r, dump));
+                }
+            }
+        }
+
+        peaks.push(match peak {
+            Some((r, dump)) => StackmarginPeak {
+                id: row.id,
+                task: row.task.clone(),
+                stacksize: r.stacksize,
+                maxdepth: r.maxdepth,
+                margin: r.margin,
+                dump: Some(dump.clone()),
+            },
+            None => StackmarginPeak {
+                id: row.id,
+                task: row.task.clone(),
+                stacksize: None,
+                maxdepth: None,
+                margin: None,
+                dump: None,
+            },
+        });
+    }
+
+    if subargs.json {
+        println!("{}", serde_json::to_string(&peaks)?);
+        return Ok(());
+    }
+
+    if subargs.yaml {
+        print!("{}", serde_yaml::to_string(&peaks)?);
+        return Ok(());
+    }
+
+    let id = |id: Option<u32>| match id {
+        Some(id) => id.to_string(),
+        None => "-".to_string(),
+    };
+
+    println!("{:2} {:18} {:>10} {:>10} {:>10} {}",
+        "ID", "TASK", "STACKSIZE", "MAXDEPTH", "MARGIN", "DUMP");
+
+    for peak in &peaks {
+        match (peak.stacksize, peak.maxdepth, peak.margin, &peak.dump) {
+            (Some(size), Some(depth), Some(margin), Some(dump)) => {
+                println!("{:>2} {:18} {:10} {:10} {:10} {}",
+                    id(peak.id), peak.task, size, depth, margin, dump);
+            }
+            _ => {
+                println!("{:>2} {:18} unknown", id(peak.id), peak.task);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// Sorts rows by the specified field.  Tasks for which we have no stack
+/// information sort last, regardless of the field that we're sorting by.
+///
+fn stackmargin_sort(rows: &mut [StackmarginRow], order: Order) {
+    match order {
+        Order::Id => {}
+        Order::Stacksize => {
+            rows.sort_by_key(|r| (r.stacksize.is_none(), r.stacksize))
+        }
+        Order::Maxdepth => {
+            rows.sort_by_key(|r| (r.maxdepth.is_none(), r.maxdepth))
+        }
+        Order::Margin => rows.sort_by_key(|r| (r.margin.is_none(), r.margin)),
+    }
+}
+
+///
+/// The idle task should use almost none of its stack; substantial depth
+/// there suggests stack misuse (e.g., by an interrupt handler) rather than
+/// anything that the idle task itself has done.  We identify the idle task
+/// by name -- or, failing that, as the last task.
+///
+fn stackmargin_idle(hubris: &HubrisArchive, rows: &[StackmarginRow], pct: u32) {
+    let idle = match hubris.lookup_task("idle") {
+        Some(HubrisTask::Task(i)) => Some(*i),
+        _ => (hubris.ntasks() as u32).checked_sub(1),
+    };
+
+    let Some(row) = rows.iter().find(|r| r.id.is_some() && r.id == idle) else {
+        return;
+    };
+
+    if let (Some(size), Some(depth)) = (row.stacksize, row.maxdepth) {
+        if size > 0 && depth as u64 * 100 > size as u64 * pct as u64 {
+            humility::warn!(
+                "idle task {} has used {} of its {} bytes of stack; this may \
+                indicate stack misuse by an interrupt handler",
+                row.task,
+                depth,
+                size
+            );
+        }
+    }
+}
+
+///
+/// Returns true if the row's margin is below the threshold (if any) and the
+/// task isn't one that we have been told to ignore.
+///
+fn below(row: &StackmarginRow, subargs: &StackmarginArgs) -> bool {
+    if subargs.ignore.contains(&row.task) {
+        return false;
+    }
+
+    match (row.margin, subargs.threshold) {
+        (Some(margin), Some(threshold)) => margin < threshold,
+        _ => false,
+    }
+}
+
+///
+/// Displays rows in the Prometheus text exposition format, with a gauge for
+/// each of stack size, maximum depth and margin, labelled by task.  Tasks
+/// whose stacks weren't read are omitted.
+///
+fn stackmargin_prometheus(rows: &[StackmarginRow]) {
+    let metrics: [(&str, &str, fn(&StackmarginRow) -> Option<u32>); 3] = [
+        ("stack_size_bytes", "Stack size", |r| r.stacksize),
+        ("stack_used_bytes", "Maximum stack depth", |r| r.maxdepth),
+        ("stack_margin_bytes", "Stack margin", |r| r.margin),
+    ];
+
+    for (name, help, value) in metrics {
+        println!("# HELP hubris_{name} {help}.");
+        println!("# TYPE hubris_{name} gauge");
+
+        for row in rows {
+            if let (true, Some(v)) = (row.captured, value(row)) {
+                let task = row.task.replace('\\', "\\\\").replace('"', "\\\"");
+                println!("hubris_{name}{{task=\"{task}\"}} {v}");
+            }
+        }
+    }
+}
+
+///
+/// Displays rows as a table.
+///
+#[rustfmt::skip::macros(println, print)]
+fn stackmargin_table(
+    hubris: &HubrisArchive,
+    core: &mut dyn Core,
+    rows: &[StackmarginRow],
+    subargs: &StackmarginArgs,
+) {
+    let id = |id: Option<u32>| match id {
+        Some(id) => id.to_string(),
+        None => "-".to_string(),
+    };
+
+    let annotations = |row: &StackmarginRow| {
+        let mut s = match row.declared {
+            Some(declared) => format!(" (declared {declared})"),
+            None => "".to_string(),
+        };
+
+        if let Some(region) = row.region {
+            s.push_str(&format!(
+                " [region 0x{:08x}-0x{:08x}, {} bytes]",
+                region.base,
+                region.base + region.size - 1,
+                region.size
+            ));
+        }
+
+        s
+    };
+
+    //
+    // When displaying to a terminal, we color the margin by its severity:
+    // red below the threshold (128 bytes by default), yellow below twice
+    // the threshold, and green otherwise.  (NO_COLOR is honored by the
+    // colored crate itself.)
+    //
+    let color = atty::is(atty::Stream::Stdout);
+    let red = subargs.threshold.unwrap_or(128);
+
+    let printer =
+        humility_stack::StackPrinter { indent: 3, ..Default::default() };
+
+    print!("{:2} {:18} {:>10} {:>10} {:>10} {:>10}",
+        "ID", "TASK", "STACKBASE", "STACKSIZE", "MAXDEPTH", "MARGIN");
+
+    let unicode = stackmargin_unicode();
+
+    if subargs.bars {
+        print!(" {:12}", "USAGE");
+    }
+
+    if subargs.percent {
+        print!(" {:>7}", "MARGIN%");
+    }
+
+    if subargs.generation {
+        print!(" {:>4}", "GEN");
+    }
+
+    if subargs.show_addr {
+        print!(" {:>10}", "DEEPEST");
+    }
+
+    if subargs.watch {
+        print!(" {:>10}", "PEAK");
+    }
+
+    println!();
+
+    for row in rows {
+        match (row.stackbase, row.stacksize, row.maxdepth, row.margin) {
+            (Some(base), Some(size), Some(depth), Some(margin)) => {
+                let m = format!("{:10}", margin);
+
+                let m = if !color {
+                    m.normal()
+                } else if margin < red {
+                    m.red()
+                } else if margin < red.saturating_mul(2) {
+                    m.yellow()
+                } else {
+                    m.green()
+                };
+
+                print!("{:>2} {:18} 0x{:<8x} {:10} {:10} {}",
+                    id(row.id), row.task, base, size, depth, m);
+
+                if subargs.bars {
+                    print!(" {}", stackmargin_bar(depth, size, unicode));
+                }
+
+                if subargs.percent {
+                    let pct = margin as f64 * 100.0 / size as f64;
+                    print!(" {:>6.1}%", pct);
+                }
+
+                if subargs.generation {
+                    match row.generation {
+                        Some(generation) => print!(" {:>4}", generation),
+                        None => print!(" {:>4}", "-"),
+                    }
+                }
+
+                if let Some(deepest) = row.deepest {
+                    print!(" 0x{:08x}", deepest);
+                }
+
+                if let Some(peak) = row.peak {
+                    print!(" {:10}", peak);
+                }
+
+                println!("{}{}{}{}",
+                    if below(row, subargs) { " !!" } else { "" },
+                    if row.overflow { " OVERFLOW?" } else { "" },
+                    match row.canary {
+                        Some(true) => " CANARY OK",
+                        Some(false) => " CANARY CLOBBERED",
+                        None => "",
+                    },
+                    annotations(row));
+
+                if let (true, Some(i)) = (subargs.backtrace, row.id) {
+                    match hubris.stack_trace(core, HubrisTask::Task(i)) {
+                        Ok(stack) => printer.print(hubris, &stack),
+                        Err(e) => {
+                            println!("   stack unwind failed: {e:?}");
+                        }
+                    }
+                }
+            }
+            (Some(base), Some(size), None, None) if row.unpainted => {
+                println!("{:>2} {:18} 0x{:<8x} {:10} {:>21}{}",
+                    id(row.id), row.task, base, size, "n/a (no fill pattern)",
+                    annotations(row));
+            }
+            (Some(base), Some(size), None, None) => {
+                println!("{:>2} {:18} 0x{:<8x} {:10} {:>10} {:>10}{}",
+                    id(row.id), row.task, base, size, "-", "not captured",
+                    annotations(row));
+            }
+            _ => {
+                println!(
+                    "{:>2} {:18} unknown (cannot read {} memory \
+                    remotely)",
+                    id(row.id), row.task,
+                    if row.id.is_some() { "supervisor" } else { "kernel" }
+                );
+            }
+        }
+    }
+
+    if subargs.summary {
+        let known = rows.iter().filter(|r| r.margin.is_some());
+        let total = |f: fn(&StackmarginRow) -> Option<u32>| {
+            known.clone().filter_map(f).sum::<u32>()
+        };
+
+        println!("{:2} {:18} {:10} {:10} {:10} {:10}",
+            "", "TOTAL", "", total(|r| r.stacksize),
+            total(|r| r.maxdepth), total(|r| r.margin));
+
+        if let Some(worst) = known.clone().min_by_key(|r| r.margin) {
+            println!("{:2} {:18} {} ({} bytes of margin)",
+                "", "WORST", worst.task, worst.margin.unwrap());
+        }
+    }
+}
+
+///
+/// Streams rows as JSON (one record per line, each with a `time` member
+/// denoting milliseconds since the Unix epoch) to a TCP listener.  Should
+/// the listener go away, we keep sampling and attempt to reconnect on
+/// each subsequent emission.
+///
+struct StackmarginEmitter {
+    addr: SocketAddr,
+    timeout: Duration,
+    stream: Option<TcpStream>,
+}
+
+impl StackmarginEmitter {
+    fn new(addr: &str, timeout: Duration) -> Result<Self> {
+        let addr = addr
+            .to_socket_addrs()
+            .with_context(|| format!("invalid address \"{addr}\""))?
+            .next()
+            .with_context(|| format!("\"{addr}\" has no addresses"))?;
+
+        let mut emitter = Self { addr, timeout, stream: None };
+
+        if !emitter.connect() {
+            bail!("could not connect to {addr}");
+        }
+
+        Ok(emitter)
+    }
+
+    fn connect(&mut self) -> bool {
+        let stream = TcpStream::connect_timeout(&self.addr, self.timeout)
+            .and_then(|s| s.set_write_timeout(Some(self.timeout)).map(|_| s));
+
+        self.stream = stream.ok();
+        self.stream.is_some()
+    }
+
+    fn emit(&mut self, rows: &[StackmarginRow]) -> Result<()> {
+        if self.stream.is_none() && !self.connect() {
+            return Ok(());
+        }
+
+        let time = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_millis() as u64;
+
+        let mut out = vec![];
+
+        for row in rows {
+            let mut record = serde_json::to_value(row)?;
+            record["time"] = time.into();
+            serde_json::to_writer(&mut out, &record)?;
+            out.push(b'\n');
+        }
+
+        if let Some(stream) = &mut self.stream {
+            if let Err(err) = stream.write_all(&out) {
+                humility::warn!("lost connection to {}: {err}", self.addr);
+                self.stream = None;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// Repeatedly computes and displays margins until interrupted, tracking the
+/// peak depth of each task across the session.
+///
+fn stackmargin_watch(
+    hubris: &HubrisArchive,
+    core: &mut dyn Core,
+    cache: &mut Option<TaskCache>,
+    subargs: &StackmarginArgs,
+) -> Result<()> {
+    if core.is_dump() {
+        bail!("can only watch a live target");
+    }
+
+    //
+    // We want to be sure that we don't leave the target halted if we are
+    // interrupted, so we catch Ctrl-C and only stop between iterations.
+    //
+    let done = humility_cmd::interrupt_flag()?;
+
+    let interval = Duration::from_millis(subargs.interval);
+    let mut peaks = BTreeMap::new();
+
+    let mut emitter = match &subargs.emit {
+        Some(addr) => Some(StackmarginEmitter::new(addr, interval)?),
+        None => None,
+    };
+
+    while !done.load(Ordering::SeqCst) {
+        //
+        // We only halt the target for as long as it takes to read the
+        // stacks, resuming it even if that fails.  While it's halted, we
+        // cache reads, as the task table is read more than once.
+        //
+        core.halt_timeout(CORE_HALT_TIMEOUT)?;
+        let enabled = core.read_cache();
+        core.set_read_cache(true);
+        let rows = stackmargin_rows(hubris, core, cache, subargs);
+        core.set_read_cache(enabled);
+        core.run()?;
+
+        let mut rows = rows?;
+
+        for row in rows.iter_mut() {
+            if let Some(depth) = row.maxdepth {
+                let peak = peaks.entry(row.task.clone()).or_insert(depth);
+                *peak = depth.max(*peak);
+                row.peak = Some(*peak);
+            }
+        }
+
+        stackmargin_sort(&mut rows, subargs.sort);
+
+        if let Some(emitter) = &mut emitter {
+            emitter.emit(&rows)?;
+        }
+
+        //
+        // Clear the screen and home the cursor before redrawing the table.
+        // (A Prometheus snapshot is instead simply emitted each interval.)
+        //
+        if subargs.prometheus {
+            stackmargin_prometheus(&rows);
+        } else {
+            print!("\x1b[2J\x1b[H");
+            stackmargin_table(hubris, core, &rows, subargs);
+        }
+
+        thread::sleep(interval);
+    }
+
+    Ok(())
+}
+
+#[rustfmt::skip::macros(print, println, bail)]
+fn stackmargin(context: &mut ExecutionContext) -> Result<()> {
+    let core = &mut **context.core.as_mut().unwrap();
+    let Subcommand::Other(subargs) = context.cli.cmd.as_ref().unwrap();
+    let hubris = context.archive.as_ref().unwrap();
+    let cache = &mut context.task_cache;
+    let interactive = context.is_interactive;
+
+    let subargs = StackmarginArgs::try_parse_from(subargs)?;
+
+    if subargs.dump_stack.is_some()
+        && subargs.dump_file.is_none()
+        && (subargs.json || subargs.yaml || subargs.csv)
+    {
+        bail!("--dump-stack with --json, --yaml or --csv requires --dump-file");
+    }
+
+    for task in &subargs.ignore {
+        if task != "kernel" && hubris.lookup_task(task).is_none() {
+            humility::warn!("ignored task \"{task}\" does not exist");
+        }
+    }
+
+    if let Some(ms) = subargs.reset_first {
+        if core.is_dump() {
+            bail!("cannot reset a dump");
+        }
+
+        core.reset()?;
+        humility::msg!("reset target; waiting {ms} ms before sampling");
+        thread::sleep(Duration::from_millis(ms));
+    }
+
+    if let Some(dir) = &subargs.fleet {
+        return stackmargin_fleet(dir, &subargs);
+    }
+
+    if !subargs.compare.is_empty() {
+        let name =
+            match context.cli.dump.as_ref().or(context.cli.image.as_ref()) {
+                Some(dump) => dump.clone(),
+                None => "(attached)".to_string(),
+            };
+
+        return stackmargin_compare(hubris, core, cache, name, &subargs);
+    }
+
+    if subargs.watch {
+        return stackmargin_watch(hubris, core, cache, &subargs);
+    }
+
+    let mut rows = stackmargin_rows(hubris, core, cache, &subargs)?;
+
+    stackmargin_sort(&mut rows, subargs.sort);
+
+    if let Some(pct) = subargs.idle_threshold {
+        stackmargin_idle(hubris, &rows, pct);
+    }
+
+    if let Some(path) = &subargs.save_baseline {
+        stackmargin_save_baseline(&rows, path)?;
+    }
+
+    if let Some(path) = &subargs.baseline {
+        return stackmargin_baseline(&rows, path, &subargs);
+    }
+
+    //
+    // To unwind a live task's stack, we need its registers to hold still;
+    // while they do, we cache reads.
+    //
+    let halt = subargs.backtrace && !core.is_dump();
+    let enabled = core.read_cache();
+
+    if halt {
+        core.halt_timeout(CORE_HALT_TIMEOUT)?;
+        core.set_read_cache(true);
+    }
+
+    if subargs.json {
+        println!("{}", serde_json::to_string(&rows)?);
+    } else if subargs.yaml {
+        print!("{}", serde_yaml::to_string(&rows)?);
+    } else if subargs.prometheus {
+        stackmargin_prometheus(&rows);
+    } else if subargs.csv {
+        let field =
+            |v: Option<u32>| v.map(|v| v.to_string()).unwrap_or_default();
+
+        println!("id,task,stackbase,stacksize,maxdepth,margin");
+
+        for row in &rows {
+            println!("{},{},{},{},{},{}",
+                field(row.id), row.task,
+                row.stackbase.map(|b| format!("0x{b:x}")).unwrap_or_default(),
+                field(row.stacksize), field(row.maxdepth), field(row.margin));
+        }
+    } else {
+        stackmargin_table(hubris, core, &rows, &subargs);
+    }
+
+    if halt {
+        core.set_read_cache(enabled);
+        core.run()?;
+    }
+
+    if let Some(task) = &subargs.dump_stack {
+        stackmargin_dump_stack(&rows, task, subargs.dump_file.as_ref())?;
+    }
+
+    if let Some(threshold) = subargs.threshold {
+        let failing = |row: &&StackmarginRow| below(row, &subargs);
+        let n = rows.iter().filter(failing).count();
+
+        if n > 0 {
+            let msg = format!(
+                "{} task{} with margin below {} bytes",
+                n,
+                if n == 1 { "" } else { "s" },
+                threshold
+            );
+
+            if subargs.exit_mask && !interactive {
+                //
+                // If every task has a bit in the exit status, we set the bit
+                // of each task below the threshold; otherwise (or if the
+                // kernel is below the threshold), we can only indicate
+                // failure.  Bit 0 is reserved for the latter case, so task N
+                // is denoted by bit N + 1.
+                //
+                let mask =
+                    rows.iter().filter(failing).try_fold(0u8, |mask, row| {
+                        match row.id {
+                            Some(id) if hubris.ntasks() <= 7 => {
+                                Some(mask | 1 << (id + 1))
+                            }
+                            _ => None,
+                        }
+                    });
+
+                let status = mask.unwrap_or(1);
+                return Err(ExitStatus { status, msg: Some(msg) }.into());
+            }
+
+            bail!(msg);
+        }
     }
 
     Ok(())
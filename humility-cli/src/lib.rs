@@ -8,6 +8,7 @@ use anyhow::Result;
 use clap::{AppSettings, ArgGroup, ArgMatches, Parser};
 use env::Environment;
 use humility::{core::Core, hubris::HubrisArchive, msg, net, warn};
+use std::collections::BTreeMap;
 
 #[derive(Parser, Debug, Clone)]
 #[clap(
@@ -63,6 +64,24 @@ pub struct Cli {
     #[clap(long, short, group = "hubris")]
     pub ip: Option<net::ScopedV6Addr>,
 
+    /// Raw memory image (e.g., a snapshot of RAM) to be interpreted with
+    /// the Hubris archive.  The address at which the image was taken must
+    /// be specified with --image-addr.
+    #[clap(
+        long,
+        value_name = "file",
+        group = "hubris",
+        requires = "image-addr"
+    )]
+    pub image: Option<String>,
+
+    /// Address of the first byte of the memory image specified with --image
+    #[clap(
+        long, value_name = "addr", requires = "image",
+        parse(try_from_str = parse_int::parse)
+    )]
+    pub image_addr: Option<u32>,
+
     /// Hubris environment file. Thie may also be set via the
     /// HUMILITY_ENVIRONMENT environment variable. Run "humility doc" for
     /// more information on Humility environments.
@@ -122,6 +141,33 @@ pub struct ExecutionContext {
     pub environment: Option<Environment>,
     pub cli: Cli,
     pub is_interactive: bool,
+    pub task_cache: Option<TaskCache>,
+}
+
+///
+/// Task table information that can be reused across commands in a session,
+/// for as long as the core's [`halted_epoch`](Core::halted_epoch) remains
+/// unchanged.
+///
+#[derive(Clone, Debug)]
+pub struct TaskCache {
+    pub epoch: u64,
+    pub task_table: (u32, u32),
+    pub initial_stacks: BTreeMap<u32, u32>,
+}
+
+impl TaskCache {
+    ///
+    /// Returns the cache if it is still valid for the specified core.
+    ///
+    pub fn valid(cache: &Option<TaskCache>, core: &dyn Core) -> Option<Self> {
+        match (cache, core.halted_epoch()) {
+            (Some(cache), Some(epoch)) if cache.epoch == epoch => {
+                Some(cache.clone())
+            }
+            _ => None,
+        }
+    }
 }
 
 impl ExecutionContext {
@@ -151,6 +197,7 @@ impl ExecutionContext {
         if cli.dump.is_none()
             && cli.probe.is_none()
             && cli.ip.is_none()
+            && cli.image.is_none()
             && cli.target.is_none()
         {
             use std::env;
@@ -315,6 +362,7 @@ impl ExecutionContext {
             environment,
             cli,
             is_interactive,
+            task_cache: None,
         })
     }
 }
@@ -2,11 +2,14 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
 use clap::Command as ClapCommand;
 use humility::core::Core;
 use humility::hubris::*;
 use humility_cli::Cli;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 
 #[allow(dead_code)]
@@ -67,7 +70,7 @@ pub fn attach_live(
     args: &Cli,
     hubris: &HubrisArchive,
 ) -> Result<Box<dyn Core>> {
-    if args.dump.is_some() {
+    if args.dump.is_some() || args.image.is_some() {
         bail!("must be run against a live system");
     } else if args.ip.is_some() {
         attach_net(args, hubris)
@@ -92,6 +95,18 @@ pub fn attach_dump(
     }
 }
 
+pub fn attach_image(
+    args: &Cli,
+    hubris: &HubrisArchive,
+) -> Result<Box<dyn Core>> {
+    match (&args.image, args.image_addr) {
+        (Some(image), Some(addr)) => {
+            humility::core::attach_image(image, addr, hubris)
+        }
+        _ => bail!("must be run against a memory image"),
+    }
+}
+
 pub fn attach_net(args: &Cli, hubris: &HubrisArchive) -> Result<Box<dyn Core>> {
     if let Some(ip) = args.ip {
         let timeout = Duration::from_millis(args.timeout as u64);
@@ -120,6 +135,8 @@ pub fn attach(
                 //
                 if context.cli.dump.is_some() {
                     attach_dump(&context.cli, hubris)
+                } else if context.cli.image.is_some() {
+                    attach_image(&context.cli, hubris)
                 } else if context.cli.probe == Some("archive".to_string()) {
                     //
                     // If our probe is set to the special "archive" token, we
@@ -173,6 +190,53 @@ pub fn attach(
     (run)(context)
 }
 
+///
+/// Returns a flag that is set when the user hits Ctrl-C, allowing a command
+/// that loops (e.g., with `--watch`) to stop between iterations rather than
+/// leaving the target halted.  A process can register only one Ctrl-C
+/// handler -- and a command may be run many times from the REPL -- so the
+/// handler is registered on the first call; each call resets the flag.
+///
+pub fn interrupt_flag() -> Result<Arc<AtomicBool>> {
+    static FLAG: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+    if let Some(flag) = FLAG.get() {
+        flag.store(false, Ordering::SeqCst);
+        return Ok(flag.clone());
+    }
+
+    let flag = Arc::new(AtomicBool::new(false));
+    let f = flag.clone();
+
+    ctrlc::set_handler(move || f.store(true, Ordering::SeqCst))
+        .context("failed to set Ctrl-C handler")?;
+
+    Ok(FLAG.get_or_init(|| flag).clone())
+}
+
+///
+/// An error that, should a command fail with it, causes humility to exit
+/// with the specified status rather than the default of 1.  If there is no
+/// message, the command has already reported its failure (e.g., as JSON),
+/// and nothing further is printed.
+///
+#[derive(Debug)]
+pub struct ExitStatus {
+    pub status: u8,
+    pub msg: Option<String>,
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.msg {
+            Some(msg) => write!(f, "{msg}"),
+            None => write!(f, "exit status {}", self.status),
+        }
+    }
+}
+
+impl std::error::Error for ExitStatus {}
+
 pub struct Dumper {
     /// Word size, in bytes
     pub size: usize,
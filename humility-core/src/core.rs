@@ -17,7 +17,7 @@ use std::fmt;
 use std::fs;
 use std::io::Read;
 use std::io::Write;
-use std::net::TcpStream;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
 use std::path::Path;
 use std::rc::Rc;
 use std::str;
@@ -27,6 +27,18 @@ use thiserror::Error;
 
 use goblin::elf::Elf;
 
+///
+/// Information about a core that has been halted.
+///
+#[derive(Copy, Clone, Debug, Default)]
+pub struct HaltInfo {
+    /// The program counter at which the core halted, if known.
+    pub pc: Option<u32>,
+}
+
+/// How long [`Core::halt`] waits for a target to halt before failing.
+pub const CORE_HALT_TIMEOUT: Duration = Duration::from_millis(1000);
+
 pub trait Core {
     fn info(&self) -> (String, Option<String>);
 
@@ -39,12 +51,33 @@ pub trait Core {
     fn write_reg(&mut self, reg: ARMRegister, value: u32) -> Result<()>;
     fn init_swv(&mut self) -> Result<()>;
     fn read_swv(&mut self) -> Result<Vec<u8>>;
-    fn write_word_32(&mut self, addr: u32, data: u32) -> Result<()>;
     fn write_8(&mut self, addr: u32, data: &[u8]) -> Result<()>;
 
     fn halt(&mut self) -> Result<()>;
     fn run(&mut self) -> Result<()>;
     fn step(&mut self) -> Result<()>;
+
+    ///
+    /// Halts the core, failing if it hasn't halted within `dur` rather than
+    /// waiting indefinitely for an unresponsive target.  Targets that can't
+    /// enforce a timeout fail by default; targets on which halting can't hang
+    /// (e.g., dumps and archives) should implement this as [`halt`].
+    ///
+    fn halt_timeout(&mut self, _dur: Duration) -> Result<HaltInfo> {
+        bail!("cannot halt with a timeout on this target");
+    }
+
+    ///
+    /// Returns true if the core is halted -- whether by us or by someone
+    /// else (e.g., a previous command or a debugger).  Callers that halt the
+    /// core can use this to avoid resuming a core that was already halted.
+    /// Targets that cannot determine their run state report that they are
+    /// running.
+    ///
+    fn is_halted(&mut self) -> Result<bool> {
+        Ok(false)
+    }
+
     fn is_dump(&self) -> bool {
         false
     }
@@ -73,6 +106,120 @@ pub trait Core {
         Ok(u64::from_le_bytes(buf))
     }
 
+    fn write_word_32(&mut self, addr: u32, data: u32) -> Result<()> {
+        self.write_8(addr, &data.to_le_bytes())
+    }
+
+    ///
+    /// Returns the largest single transfer that the transport can perform
+    /// efficiently (and reliably); callers with large reads should chunk
+    /// them accordingly.  By default, this is a conservative
+    /// [`CORE_DEFAULT_TRANSFER_SIZE`].
+    ///
+    fn max_transfer_size(&self) -> usize {
+        CORE_DEFAULT_TRANSFER_SIZE
+    }
+
+    ///
+    /// Reads into `data` in chunks of [`max_transfer_size`] bytes, calling
+    /// `progress` with the total number of bytes read after each chunk.
+    ///
+    fn read_8_progress(
+        &mut self,
+        addr: u32,
+        data: &mut [u8],
+        progress: &mut dyn FnMut(usize),
+    ) -> Result<()> {
+        let mut offs = 0;
+
+        for chunk in data.chunks_mut(self.max_transfer_size()) {
+            self.read_8(addr + offs as u32, chunk)?;
+            offs += chunk.len();
+            progress(offs);
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Reads a batch of `(addr, len)` requests, returning a buffer for each.
+    /// By default, this issues a [`read_8`] per request (or per chunk of
+    /// [`max_transfer_size`] bytes, for large requests), but transports for
+    /// which each read is expensive may coalesce requests into fewer reads.
+    ///
+    fn read_batch(
+        &mut self,
+        requests: &[(u32, usize)],
+    ) -> Result<Vec<Vec<u8>>> {
+        let max = self.max_transfer_size();
+
+        requests
+            .iter()
+            .map(|&(addr, len)| -> Result<Vec<u8>> {
+                let mut buf = vec![0; len];
+
+                for (i, chunk) in buf.chunks_mut(max).enumerate() {
+                    self.read_8(addr + (i * max) as u32, chunk)?;
+                }
+
+                Ok(buf)
+            })
+            .collect()
+    }
+
+    ///
+    /// Enables (or disables) caching of reads while the core is halted.
+    /// When enabled, reads made while the core is explicitly halted are
+    /// memoized by address and length; the cache is discarded on any call
+    /// that may change the state of the target.  Caching is disabled by
+    /// default, and is a no-op for targets that don't support it.
+    ///
+    fn set_read_cache(&mut self, _enabled: bool) {}
+
+    /// Returns true if caching of reads is enabled.
+    fn read_cache(&self) -> bool {
+        false
+    }
+
+    /// Discard any cached reads.
+    fn invalidate_cache(&mut self) {}
+
+    ///
+    /// Returns a value that changes whenever the target may have changed
+    /// state (e.g., by running) since it was halted, or `None` if the target
+    /// isn't halted or its state can't be tracked.  Anything derived from
+    /// target memory remains valid for as long as this value is unchanged.
+    ///
+    fn halted_epoch(&self) -> Option<u64> {
+        None
+    }
+
+    ///
+    /// Selects the core that subsequent operations apply to on a multicore
+    /// target.  Targets that have only a single core support only core 0.
+    ///
+    fn select_core(&mut self, index: usize) -> Result<()> {
+        if index >= self.num_cores() {
+            bail!("cannot select core {index}: target has a single core");
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// Returns the number of cores that can be passed to `select_core`.
+    ///
+    fn num_cores(&self) -> usize {
+        1
+    }
+
+    ///
+    /// Returns the index of the currently selected core.
+    ///
+    fn selected_core(&self) -> usize {
+        0
+    }
+
     ///
     /// Called to load a flash image.
     ///
@@ -222,6 +369,9 @@ pub struct ProbeCore {
     unhalted_reads: bool,
     halted: u32,
     unhalted_read: BTreeMap<u32, u32>,
+    read_cache: Option<HashMap<(u32, usize), Vec<u8>>>,
+    epoch: u64,
+    core_index: usize,
     can_flash: bool,
 }
 
@@ -244,6 +394,9 @@ impl ProbeCore {
             unhalted_reads,
             halted: 0,
             unhalted_read: humility_arch_arm::unhalted_read_regions(),
+            read_cache: None,
+            epoch: 0,
+            core_index: 0,
             can_flash,
         }
     }
@@ -252,7 +405,7 @@ impl ProbeCore {
         &mut self,
         mut func: impl FnMut(&mut probe_rs::Core) -> Result<()>,
     ) -> Result<()> {
-        let mut core = self.session.core(0)?;
+        let mut core = self.session.core(self.core_index)?;
 
         if self.unhalted_reads {
             func(&mut core)
@@ -276,6 +429,7 @@ impl ProbeCore {
 }
 
 pub const CORE_MAX_READSIZE: usize = 65536; // 64K ought to be enough for anyone
+pub const CORE_DEFAULT_TRANSFER_SIZE: usize = 4096;
 
 #[rustfmt::skip::macros(anyhow, bail)]
 impl Core for ProbeCore {
@@ -292,13 +446,17 @@ impl Core for ProbeCore {
         Some((self.vendor_id, self.product_id))
     }
 
+    fn max_transfer_size(&self) -> usize {
+        CORE_MAX_READSIZE
+    }
+
     fn read_word_32(&mut self, addr: u32) -> Result<u32> {
         log::trace!("reading word at {:x}", addr);
         let mut rval = 0;
 
         if let Some(range) = self.unhalted_read.range(..=addr).next_back() {
             if addr + 4 < range.0 + range.1 {
-                let mut core = self.session.core(0)?;
+                let mut core = self.session.core(self.core_index)?;
                 return core.read_word_32(addr).with_context(|| {
                     format!(
                         "failed to perform unhalted word read at address \
@@ -329,7 +487,7 @@ impl Core for ProbeCore {
 
         if let Some(range) = self.unhalted_read.range(..=addr).next_back() {
             if addr + (data.len() as u32) < range.0 + range.1 {
-                let mut core = self.session.core(0)?;
+                let mut core = self.session.core(self.core_index)?;
                 return core.read_8(addr, data).with_context(|| {
                     format!(
                         "failed to perform unhalted read at address \
@@ -340,6 +498,21 @@ impl Core for ProbeCore {
             }
         }
 
+        //
+        // If we're explicitly halted and caching is enabled, memory can't
+        // change out from under us: return (or remember) what we've read.
+        //
+        let key = (addr, data.len());
+
+        if self.halted > 0 {
+            if let Some(buf) =
+                self.read_cache.as_ref().and_then(|c| c.get(&key))
+            {
+                data.copy_from_slice(buf);
+                return Ok(());
+            }
+        }
+
         self.halt_and_read(|core| {
             core.read_8(addr, data).with_context(|| {
                 format!(
@@ -348,11 +521,19 @@ impl Core for ProbeCore {
                     data.len()
                 )
             })
-        })
+        })?;
+
+        if self.halted > 0 {
+            if let Some(ref mut cache) = self.read_cache {
+                cache.insert(key, data.to_vec());
+            }
+        }
+
+        Ok(())
     }
 
     fn read_reg(&mut self, reg: ARMRegister) -> Result<u32> {
-        let mut core = self.session.core(0)?;
+        let mut core = self.session.core(self.core_index)?;
         use num_traits::ToPrimitive;
 
         Ok(core.read_core_reg(Into::<probe_rs::CoreRegisterAddress>::into(
@@ -361,7 +542,9 @@ impl Core for ProbeCore {
     }
 
     fn write_reg(&mut self, reg: ARMRegister, value: u32) -> Result<()> {
-        let mut core = self.session.core(0)?;
+        self.invalidate_cache();
+
+        let mut core = self.session.core(self.core_index)?;
         use num_traits::ToPrimitive;
 
         core.write_core_reg(
@@ -375,32 +558,111 @@ impl Core for ProbeCore {
     }
 
     fn write_word_32(&mut self, addr: u32, data: u32) -> Result<()> {
-        let mut core = self.session.core(0)?;
+        self.invalidate_cache();
+
+        let mut core = self.session.core(self.core_index)?;
         core.write_word_32(addr, data)?;
         Ok(())
     }
 
     fn write_8(&mut self, addr: u32, data: &[u8]) -> Result<()> {
-        let mut core = self.session.core(0)?;
+        self.invalidate_cache();
+
+        let mut core = self.session.core(self.core_index)?;
         core.write_8(addr, data)?;
         Ok(())
     }
 
+    fn set_read_cache(&mut self, enabled: bool) {
+        if enabled != self.read_cache.is_some() {
+            self.read_cache = if enabled { Some(HashMap::new()) } else { None };
+        }
+    }
+
+    fn read_cache(&self) -> bool {
+        self.read_cache.is_some()
+    }
+
+    fn invalidate_cache(&mut self) {
+        self.epoch += 1;
+
+        if let Some(ref mut cache) = self.read_cache {
+            cache.clear();
+        }
+    }
+
+    fn halted_epoch(&self) -> Option<u64> {
+        if self.halted > 0 {
+            Some(self.epoch)
+        } else {
+            None
+        }
+    }
+
+    fn select_core(&mut self, index: usize) -> Result<()> {
+        if self.halted != 0 {
+            bail!("cannot select a different core while halted");
+        }
+
+        let ncores = self.num_cores();
+
+        if index >= ncores {
+            bail!("cannot select core {index}: target has {ncores} cores");
+        }
+
+        self.session
+            .core(index)
+            .with_context(|| format!("cannot select core {index}"))?;
+
+        self.core_index = index;
+        self.invalidate_cache();
+        Ok(())
+    }
+
+    fn num_cores(&self) -> usize {
+        self.session.list_cores().len()
+    }
+
+    fn selected_core(&self) -> usize {
+        self.core_index
+    }
+
     fn halt(&mut self) -> Result<()> {
+        self.halt_timeout(CORE_HALT_TIMEOUT)?;
+        Ok(())
+    }
+
+    fn halt_timeout(&mut self, dur: Duration) -> Result<HaltInfo> {
+        let mut info = HaltInfo::default();
+
         if self.halted == 0 {
-            let mut core = self.session.core(0)?;
-            core.halt(std::time::Duration::from_millis(1000))?;
+            self.invalidate_cache();
+            let mut core = self.session.core(self.core_index)?;
+            let halted = core.halt(dur).with_context(|| {
+                format!("core did not halt within {} ms", dur.as_millis())
+            })?;
+            info.pc = Some(halted.pc);
         }
 
         self.halted += 1;
-        Ok(())
+        Ok(info)
+    }
+
+    fn is_halted(&mut self) -> Result<bool> {
+        if self.halted > 0 {
+            return Ok(true);
+        }
+
+        let mut core = self.session.core(self.core_index)?;
+        Ok(core.core_halted()?)
     }
 
     fn run(&mut self) -> Result<()> {
         self.halted -= 1;
 
         if self.halted == 0 {
-            let mut core = self.session.core(0)?;
+            self.invalidate_cache();
+            let mut core = self.session.core(self.core_index)?;
             core.run()?;
         }
 
@@ -408,7 +670,9 @@ impl Core for ProbeCore {
     }
 
     fn step(&mut self) -> Result<()> {
-        let mut core = self.session.core(0)?;
+        self.invalidate_cache();
+
+        let mut core = self.session.core(self.core_index)?;
         core.step()?;
         Ok(())
     }
@@ -454,6 +718,8 @@ impl Core for ProbeCore {
             bail!("cannot flash without explicitly attaching to flash");
         }
 
+        self.invalidate_cache();
+
         let progress =
             Rc::new(RefCell::new(LoadProgress { ..Default::default() }));
 
@@ -522,13 +788,17 @@ impl Core for ProbeCore {
     }
 
     fn reset(&mut self) -> Result<()> {
-        let mut core = self.session.core(0)?;
+        self.invalidate_cache();
+
+        let mut core = self.session.core(self.core_index)?;
         core.reset()?;
         Ok(())
     }
 
     fn reset_and_halt(&mut self, dur: std::time::Duration) -> Result<()> {
-        let mut core = self.session.core(0)?;
+        self.invalidate_cache();
+
+        let mut core = self.session.core(self.core_index)?;
         core.reset_and_halt(dur)?;
         Ok(())
     }
@@ -551,7 +821,8 @@ impl Core for ProbeCore {
 
     fn wait_for_halt(&mut self, dur: std::time::Duration) -> Result<()> {
         if self.halted == 0 {
-            let mut core = self.session.core(0)?;
+            self.invalidate_cache();
+            let mut core = self.session.core(self.core_index)?;
             core.wait_for_core_halted(dur)?;
         }
 
@@ -834,6 +1105,13 @@ impl Core for OpenOCDCore {
         Ok(())
     }
 
+    fn halt_timeout(&mut self, _dur: Duration) -> Result<HaltInfo> {
+        //
+        // As we don't halt (see above), there is nothing to wait for.
+        //
+        Ok(HaltInfo::default())
+    }
+
     fn run(&mut self) -> Result<()> {
         //
         // Well, see above.
@@ -866,18 +1144,20 @@ impl Core for OpenOCDCore {
 enum GDBServer {
     OpenOCD,
     JLink,
+    Remote(SocketAddr),
 }
 
+/// The address of a generic GDB server absent one being specified; this is
+/// the default for QEMU's GDB stub.
+const GDB_REMOTE_DEFAULT: &str = "127.0.0.1:1234";
+
 impl fmt::Display for GDBServer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                GDBServer::OpenOCD => "OpenOCD",
-                GDBServer::JLink => "JLink",
-            }
-        )
+        match self {
+            GDBServer::OpenOCD => write!(f, "OpenOCD"),
+            GDBServer::JLink => write!(f, "JLink"),
+            GDBServer::Remote(addr) => write!(f, "remote ({addr})"),
+        }
     }
 }
 
@@ -892,6 +1172,15 @@ const GDB_PACKET_END: char = '#';
 const GDB_PACKET_ACK: char = '+';
 const GDB_PACKET_HALT: u8 = 3;
 
+fn is_timeout(err: &anyhow::Error) -> bool {
+    use std::io::ErrorKind;
+
+    matches!(
+        err.downcast_ref::<std::io::Error>().map(|e| e.kind()),
+        Some(ErrorKind::WouldBlock | ErrorKind::TimedOut)
+    )
+}
+
 #[rustfmt::skip::macros(anyhow, bail)]
 impl GDBCore {
     fn prepcmd(&mut self, cmd: &str) -> Vec<u8> {
@@ -1051,13 +1340,12 @@ impl GDBCore {
     }
 
     fn new(server: GDBServer) -> Result<GDBCore> {
-        let port = match server {
-            GDBServer::OpenOCD => 3333,
-            GDBServer::JLink => 2331,
+        let addr = match server {
+            GDBServer::OpenOCD => "127.0.0.1:3333".parse()?,
+            GDBServer::JLink => "127.0.0.1:2331".parse()?,
+            GDBServer::Remote(addr) => addr,
         };
 
-        let host = format!("127.0.0.1:{}", port);
-        let addr = host.parse()?;
         let timeout = Duration::from_millis(100);
 
         let stream =
@@ -1065,15 +1353,15 @@ impl GDBCore {
                 anyhow!(
                 "can't connect to {} GDB server on \
                     port {}; is it running?",
-                server, port
+                server, addr.port()
             )
             })?;
 
         //
-        // Both the OpenOCD and JLink GDB servers stop the target upon
-        // connection.  This is helpful in that we know the state that
-        // we're in -- but it's also not the state that we want to be
-        // in.  We explicitly run the target before returning.
+        // The OpenOCD and JLink GDB servers (as well as QEMU's GDB stub)
+        // stop the target upon connection.  This is helpful in that we know
+        // the state that we're in -- but it's also not the state that we
+        // want to be in.  We explicitly run the target before returning.
         //
         let mut core = Self { stream, server, halted: true };
 
@@ -1131,32 +1419,97 @@ impl Core for GDBCore {
         rval
     }
 
-    fn write_reg(&mut self, _reg: ARMRegister, _value: u32) -> Result<()> {
-        Err(anyhow!(
-            "{} GDB target does not support modifying state", self.server
-        ))
+    fn write_reg(&mut self, reg: ARMRegister, value: u32) -> Result<()> {
+        use num_traits::ToPrimitive;
+
+        //
+        // As with memory, we only modify registers on a generic GDB server.
+        //
+        if !matches!(self.server, GDBServer::Remote(_)) {
+            return Err(anyhow!(
+                "{} GDB target does not support modifying state", self.server
+            ));
+        }
+
+        let mut cmd = format!("P{:x}=", ARMRegister::to_u16(&reg).unwrap());
+
+        for b in value.to_le_bytes() {
+            cmd.push_str(&format!("{:02x}", b));
+        }
+
+        let rstr = self.sendcmd(&cmd)?;
+
+        if rstr != "OK" {
+            bail!("bad write_reg on cmd {}: {}", cmd, rstr);
+        }
+
+        Ok(())
     }
 
-    fn write_word_32(&mut self, _addr: u32, _data: u32) -> Result<()> {
-        Err(anyhow!(
-            "{} GDB target does not support modifying state", self.server
-        ))
+    fn write_word_32(&mut self, addr: u32, data: u32) -> Result<()> {
+        self.write_8(addr, &data.to_le_bytes())
     }
 
-    fn write_8(&mut self, _addr: u32, _data: &[u8]) -> Result<()> {
-        Err(anyhow!(
-            "{} GDB target does not support modifying state", self.server
-        ))
+    fn write_8(&mut self, addr: u32, data: &[u8]) -> Result<()> {
+        //
+        // We only modify state on a generic GDB server (e.g., a simulator);
+        // the debugger behind an OpenOCD or JLink GDB server is presumed to
+        // be in use by someone else.
+        //
+        if !matches!(self.server, GDBServer::Remote(_)) {
+            return Err(anyhow!(
+                "{} GDB target does not support modifying state", self.server
+            ));
+        }
+
+        let mut cmd = format!("M{:x},{:x}:", addr, data.len());
+
+        for b in data {
+            cmd.push_str(&format!("{:02x}", b));
+        }
+
+        let rstr = self.sendcmd(&cmd)?;
+
+        if rstr != "OK" {
+            bail!("bad write_8 on cmd {}: {}", cmd, rstr);
+        }
+
+        Ok(())
     }
 
     fn halt(&mut self) -> Result<()> {
+        self.halt_timeout(CORE_HALT_TIMEOUT)?;
+        Ok(())
+    }
+
+    fn halt_timeout(&mut self, dur: Duration) -> Result<HaltInfo> {
+        //
+        // The stop reply doesn't arrive until the target has halted, so we
+        // bound our wait for it with a read timeout.  If we time out, the
+        // reply may yet arrive and be mistaken for the response to a later
+        // command; the connection should not be used further.
+        //
+        self.stream.set_read_timeout(Some(dur))?;
         self.stream.write_all(&[GDB_PACKET_HALT])?;
 
-        let reply = self.recv(false)?;
+        let reply = self.recv(false);
+        self.stream.set_read_timeout(None)?;
+
+        let reply = match reply {
+            Err(err) if is_timeout(&err) => {
+                bail!("target did not halt within {} ms", dur.as_millis());
+            }
+            reply => reply?,
+        };
+
         log::trace!("halt reply: {}", reply);
         self.halted = true;
 
-        Ok(())
+        Ok(HaltInfo::default())
+    }
+
+    fn is_halted(&mut self) -> Result<bool> {
+        Ok(self.halted)
     }
 
     fn run(&mut self) -> Result<()> {
@@ -1265,6 +1618,15 @@ impl Core for DumpCore {
         ("core dump".to_string(), None)
     }
 
+    fn max_transfer_size(&self) -> usize {
+        usize::MAX
+    }
+
+    fn halted_epoch(&self) -> Option<u64> {
+        // A dump never changes.
+        Some(0)
+    }
+
     fn read_8(&mut self, addr: u32, data: &mut [u8]) -> Result<()> {
         let rsize = data.len();
 
@@ -1330,10 +1692,18 @@ impl Core for DumpCore {
         Ok(())
     }
 
+    fn halt_timeout(&mut self, _dur: Duration) -> Result<HaltInfo> {
+        Ok(HaltInfo::default())
+    }
+
     fn run(&mut self) -> Result<()> {
         Ok(())
     }
 
+    fn is_halted(&mut self) -> Result<bool> {
+        Ok(true)
+    }
+
     fn step(&mut self) -> Result<()> {
         bail!("can't step a dump");
     }
@@ -1367,6 +1737,148 @@ impl Core for DumpCore {
     }
 }
 
+///
+/// A core backed by a flat memory image (e.g., a snapshot of RAM) that was
+/// loaded at a known address.  Reads outside of the image are satisfied from
+/// the archive's flash, if possible.
+///
+pub struct ImageCore {
+    base: u32,
+    contents: Vec<u8>,
+    flash: HubrisFlashMap,
+}
+
+impl ImageCore {
+    fn new(
+        image: &str,
+        base: u32,
+        hubris: &HubrisArchive,
+    ) -> Result<ImageCore> {
+        let contents = fs::read(image)
+            .with_context(|| format!("failed to read image {}", image))?;
+
+        if contents.is_empty() {
+            bail!("image {} is empty", image);
+        }
+
+        if base as u64 + contents.len() as u64 > u32::MAX as u64 + 1 {
+            bail!(
+                "image {} ({} bytes) at 0x{:x} exceeds the address space",
+                image,
+                contents.len(),
+                base
+            );
+        }
+
+        Ok(Self { base, contents, flash: HubrisFlashMap::new(hubris)? })
+    }
+}
+
+impl Core for ImageCore {
+    fn info(&self) -> (String, Option<String>) {
+        ("memory image".to_string(), None)
+    }
+
+    fn max_transfer_size(&self) -> usize {
+        usize::MAX
+    }
+
+    fn halted_epoch(&self) -> Option<u64> {
+        // Like a dump, an image never changes.
+        Some(0)
+    }
+
+    fn read_8(&mut self, addr: u32, data: &mut [u8]) -> Result<()> {
+        let offs = addr.wrapping_sub(self.base) as usize;
+
+        if addr >= self.base && offs + data.len() <= self.contents.len() {
+            data.copy_from_slice(&self.contents[offs..offs + data.len()]);
+            return Ok(());
+        }
+
+        if self.flash.read(addr, data).is_some() {
+            return Ok(());
+        }
+
+        bail!(
+            "read of {} bytes from 0x{:x} is outside of image \
+            (0x{:x}-0x{:x}) and flash",
+            data.len(),
+            addr,
+            self.base,
+            self.base as u64 + self.contents.len() as u64 - 1
+        );
+    }
+
+    fn read_reg(&mut self, reg: ARMRegister) -> Result<u32> {
+        bail!("cannot read register {} from a memory image", reg);
+    }
+
+    fn write_reg(&mut self, reg: ARMRegister, _value: u32) -> Result<()> {
+        bail!("cannot write register {} to a memory image", reg);
+    }
+
+    fn write_word_32(&mut self, _addr: u32, _data: u32) -> Result<()> {
+        bail!("cannot write a word to a memory image");
+    }
+
+    fn write_8(&mut self, _addr: u32, _data: &[u8]) -> Result<()> {
+        bail!("cannot write a byte to a memory image");
+    }
+
+    fn halt(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn halt_timeout(&mut self, _dur: Duration) -> Result<HaltInfo> {
+        Ok(HaltInfo::default())
+    }
+
+    fn run(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_halted(&mut self) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn step(&mut self) -> Result<()> {
+        bail!("can't step a memory image");
+    }
+
+    fn init_swv(&mut self) -> Result<()> {
+        bail!("cannot enable SWV on a memory image");
+    }
+
+    fn read_swv(&mut self) -> Result<Vec<u8>> {
+        bail!("cannot read SWV on a memory image");
+    }
+
+    fn is_dump(&self) -> bool {
+        //
+        // For our purposes, an image is a dump without registers: it can't
+        // be run, halted, or otherwise changed.
+        //
+        true
+    }
+
+    fn load(&mut self, _path: &Path) -> Result<()> {
+        bail!("Flash loading is not supported on a memory image");
+    }
+
+    fn reset(&mut self) -> Result<()> {
+        bail!("Reset is not supported on a memory image");
+    }
+
+    fn reset_and_halt(&mut self, _dur: std::time::Duration) -> Result<()> {
+        bail!("Reset is not supported on a memory image");
+    }
+
+    fn wait_for_halt(&mut self, _dur: std::time::Duration) -> Result<()> {
+        bail!("Wait for halt is not supported on a memory image");
+    }
+}
+
 pub struct ArchiveCore {
     flash: HubrisFlashMap,
 }
@@ -1392,6 +1904,10 @@ impl Core for ArchiveCore {
         true
     }
 
+    fn max_transfer_size(&self) -> usize {
+        usize::MAX
+    }
+
     fn info(&self) -> (String, Option<String>) {
         ("archive".to_string(), None)
     }
@@ -1420,10 +1936,18 @@ impl Core for ArchiveCore {
         Ok(())
     }
 
+    fn halt_timeout(&mut self, _dur: Duration) -> Result<HaltInfo> {
+        Ok(HaltInfo::default())
+    }
+
     fn run(&mut self) -> Result<()> {
         Ok(())
     }
 
+    fn is_halted(&mut self) -> Result<bool> {
+        Ok(true)
+    }
+
     fn step(&mut self) -> Result<()> {
         bail!("can't step an archive");
     }
@@ -1549,6 +2073,9 @@ pub fn attach_to_probe(probe: &str) -> Result<Box<dyn Core>> {
         "ocd" | "ocdgdb" | "jlink" => {
             bail!("Probe only attachment with {} is not supported", probe)
         }
+        p if p == "gdb" || p.starts_with("gdb:") => {
+            bail!("Probe only attachment with {} is not supported", probe)
+        }
         "auto" => attach_to_probe("usb"),
         _ => match TryInto::<probe_rs::DebugProbeSelector>::try_into(probe) {
             Ok(selector) => {
@@ -1661,6 +2188,21 @@ pub fn attach_to_chip(
             Ok(Box::new(core))
         }
 
+        p if p == "gdb" || p.starts_with("gdb:") => {
+            let addr = p.strip_prefix("gdb:").unwrap_or(GDB_REMOTE_DEFAULT);
+
+            let addr = addr
+                .to_socket_addrs()
+                .ok()
+                .and_then(|mut addrs| addrs.next())
+                .ok_or_else(|| anyhow!("invalid GDB server address: {addr}"))?;
+
+            let core = GDBCore::new(GDBServer::Remote(addr))?;
+            crate::msg!("attached via GDB server at {addr}");
+
+            Ok(Box::new(core))
+        }
+
         _ => match TryInto::<probe_rs::DebugProbeSelector>::try_into(probe) {
             Ok(selector) => {
                 let vidpid = probe;
@@ -1722,6 +2264,16 @@ pub fn attach_dump(
     Ok(Box::new(core))
 }
 
+pub fn attach_image(
+    image: &str,
+    base: u32,
+    hubris: &HubrisArchive,
+) -> Result<Box<dyn Core>> {
+    let core = ImageCore::new(image, base, hubris)?;
+    crate::msg!("attached to memory image at 0x{:x}", base);
+    Ok(Box::new(core))
+}
+
 pub fn attach_archive(hubris: &HubrisArchive) -> Result<Box<dyn Core>> {
     let core = ArchiveCore::new(hubris)?;
     crate::msg!("attached to archive");
@@ -18,6 +18,7 @@ use std::mem::size_of;
 use std::num::TryFromIntError;
 use std::path::Path;
 use std::str::{self, FromStr};
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 use crate::{msg, warn};
@@ -42,6 +43,10 @@ const OXIDE_NT_HUBRIS_TASK: u32 = OXIDE_NT_BASE + 3;
 
 const MAX_HUBRIS_VERSION: u32 = 8;
 
+/// The pattern with which the kernel fills uninitialized stack, absent a
+/// pattern specified in the kernel's configuration.
+pub const HUBRIS_STACK_FILL: u32 = 0xbaddcafe;
+
 #[derive(Default, Debug, Serialize)]
 pub struct HubrisManifest {
     pub version: Option<String>,
@@ -54,12 +59,14 @@ pub struct HubrisManifest {
     pub task_features: HashMap<String, Vec<String>>,
     pub task_irqs: HashMap<String, Vec<(u32, u32)>>,
     pub task_notifications: HashMap<String, Vec<String>>,
+    pub task_stacksize: HashMap<String, u32>,
     pub peripherals: BTreeMap<String, u32>,
     pub peripherals_byaddr: BTreeMap<u32, String>,
     pub i2c_devices: Vec<HubrisI2cDevice>,
     pub i2c_buses: Vec<HubrisI2cBus>,
     pub sensors: Vec<HubrisSensor>,
     pub auxflash: Option<HubrisConfigAuxflash>,
+    pub stack_fill: Option<u32>,
 }
 
 //
@@ -79,6 +86,7 @@ struct HubrisConfig {
     peripherals: Option<IndexMap<String, HubrisConfigPeripheral>>,
     chip: Option<String>,
     config: Option<HubrisConfigConfig>,
+    stacksize: Option<u32>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -99,14 +107,17 @@ struct HubrisMemoryRegion {
 }
 
 #[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 struct HubrisConfigKernel {
     features: Option<Vec<String>>,
+    stack_fill: Option<u32>,
 }
 
 #[derive(Clone, Debug, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 struct HubrisConfigTask {
     features: Option<Vec<String>>,
+    stacksize: Option<u32>,
     extern_regions: Option<Vec<String>>,
     #[serde(default)]
     notifications: Vec<String>,
@@ -609,6 +620,12 @@ pub struct HubrisArchive {
     // loaded regions
     loaded: BTreeMap<u32, HubrisRegion>,
 
+    // kernel stack, as a base and size
+    kstack: Option<(u32, u32)>,
+
+    // endianness of the kernel
+    endianness: Option<HubrisEndianness>,
+
     // current object
     current: u32,
 
@@ -667,6 +684,10 @@ pub struct HubrisArchive {
     // Structures: name to goff
     structs_byname: MultiMap<String, HubrisGoff>,
 
+    // Structures: name to goff of the (deduplicated) structure, as resolved
+    // by lookup_struct_byname(); populated lazily and cleared on load
+    structs_resolved: Mutex<HashMap<String, HubrisGoff>>,
+
     // Enums: goff to enum
     enums: HashMap<HubrisGoff, HubrisEnum>,
 
@@ -707,6 +728,8 @@ impl HubrisArchive {
             imageid: None,
             manifest: Default::default(),
             loaded: BTreeMap::new(),
+            kstack: None,
+            endianness: None,
             current: 0,
             task_dump: None,
             instrs: HashMap::new(),
@@ -726,6 +749,7 @@ impl HubrisArchive {
             ptrtypes: HashMap::new(),
             structs: HashMap::new(),
             structs_byname: MultiMap::new(),
+            structs_resolved: Mutex::new(HashMap::new()),
             enums: HashMap::new(),
             enums_byname: MultiMap::new(),
             arrays: HashMap::new(),
@@ -1063,6 +1087,7 @@ impl HubrisArchive {
         };
         self.manifest.auxflash =
             config.config.as_ref().and_then(|c| c.auxflash.clone());
+        self.manifest.stack_fill = config.kernel.stack_fill;
 
         let mut named_interrupts = HashMap::new();
 
@@ -1135,6 +1160,13 @@ impl HubrisArchive {
             self.manifest
                 .task_notifications
                 .insert(name.clone(), task.notifications.clone());
+
+            //
+            // A task without a stack size of its own gets the default.
+            //
+            if let Some(stacksize) = task.stacksize.or(config.stacksize) {
+                self.manifest.task_stacksize.insert(name.clone(), stacksize);
+            }
         }
 
         if let Some(ref config) = config.config {
@@ -1261,7 +1293,31 @@ impl HubrisArchive {
         //
         let mut buffer = Vec::new();
         byname!("elf/kernel")?.read_to_end(&mut buffer)?;
+
+        //
+        // If objects were built with split DWARF, their split debug objects
+        // (.dwo files) may have been included in the archive; we gather them
+        // (by file name) so that each object can find its own.
+        //
+        let mut dwos = HashMap::new();
+
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+
+            if !file.name().ends_with(".dwo") {
+                continue;
+            }
+
+            let name = file.name().rsplit('/').next().unwrap().to_owned();
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+            dwos.insert(name, buf);
+        }
+
+        let dwos = Arc::new(dwos);
+
         let mut loader = HubrisObjectLoader::new(self.current)?;
+        loader.dwos = dwos.clone();
         loader.load_object("kernel", HubrisTask::Kernel, &buffer)?;
         self.merge(loader)?;
 
@@ -1318,6 +1374,7 @@ impl HubrisArchive {
             .map(|(id, name, buf)| {
                 let id: u32 = id.try_into().unwrap();
                 let mut loader = HubrisObjectLoader::new(self.current + id)?;
+                loader.dwos = dwos.clone();
                 loader.load_object(&name, HubrisTask::Task(id), &buf)?;
                 Ok(loader)
             })
@@ -1368,6 +1425,8 @@ impl HubrisArchive {
             self.structs_byname.insert(name.clone(), *goff);
         }
 
+        self.structs_resolved.get_mut().unwrap().clear();
+
         Ok(())
     }
 
@@ -1413,6 +1472,12 @@ impl HubrisArchive {
         if loader.apptable.is_some() {
             self.apptable = loader.apptable;
         }
+        if loader.kstack.is_some() {
+            self.kstack = loader.kstack;
+        }
+        if loader.endianness.is_some() {
+            self.endianness = loader.endianness;
+        }
         self.esyms_byname.extend(loader.esyms_byname);
 
         self.esyms.extend(loader.esyms);
@@ -1426,6 +1491,7 @@ impl HubrisArchive {
         self.src.extend(loader.src);
         self.enums_byname.extend(loader.enums_byname);
         self.structs_byname.extend(loader.structs_byname);
+        self.structs_resolved.get_mut().unwrap().clear();
         self.arrays.extend(loader.arrays);
         self.basetypes.extend(loader.basetypes);
         self.basetypes_byname.extend(loader.basetypes_byname);
@@ -1754,6 +1820,27 @@ impl HubrisArchive {
         Ok(())
     }
 
+    ///
+    /// Loads the symbols and debug information from a standalone ELF object
+    /// (e.g., a locally built binary), named by its file stem.
+    ///
+    pub fn load_elf(&mut self, elf: &str) -> Result<()> {
+        if !self.modules.is_empty() {
+            bail!("cannot specify both an archive and an ELF object");
+        }
+
+        let contents = fs::read(elf)?;
+
+        let name =
+            Path::new(elf).file_stem().and_then(|s| s.to_str()).unwrap_or(elf);
+
+        let mut loader = HubrisObjectLoader::new(self.current)?;
+        loader.load_object(name, HubrisTask::Kernel, &contents)?;
+        self.merge(loader)?;
+
+        Ok(())
+    }
+
     pub fn loaded(&self) -> bool {
         !self.modules.is_empty()
     }
@@ -1794,7 +1881,14 @@ impl HubrisArchive {
     /// present, and be present exactly once.  If needed structures begin
     /// having their names duplicated in modules, we may need to support
     /// proper namespacing -- or kludgey namespacing...
+    ///
+    /// Deduplicating the structures of a given name requires comparing them
+    /// member by member, so the result is cached.
     pub fn lookup_struct_byname(&self, name: &str) -> Result<&HubrisStruct> {
+        if let Some(goff) = self.structs_resolved.lock().unwrap().get(name) {
+            return Ok(self.structs.get(goff).unwrap());
+        }
+
         match self.structs_byname.get_vec(name) {
             Some(v) => {
                 let m = self.dedup(v.iter())?;
@@ -1802,6 +1896,11 @@ impl HubrisArchive {
                 if m.len() > 1 {
                     Err(anyhow!("{} matches more than one structure", name))
                 } else {
+                    self.structs_resolved
+                        .lock()
+                        .unwrap()
+                        .insert(name.to_string(), m[0]);
+
                     Ok(self.structs.get(&m[0]).unwrap())
                 }
             }
@@ -1855,6 +1954,17 @@ impl HubrisArchive {
         }
     }
 
+    ///
+    /// Returns the members of the specified type, which are empty if the type
+    /// is not a structure.
+    ///
+    pub fn members(&self, goff: HubrisGoff) -> &[HubrisStructMember] {
+        match self.structs.get(&goff) {
+            Some(s) => &s.members,
+            None => &[],
+        }
+    }
+
     pub fn lookup_array(&self, goff: HubrisGoff) -> Result<&HubrisArray> {
         match self.arrays.get(&goff) {
             Some(array) => Ok(array),
@@ -1939,6 +2049,31 @@ impl HubrisArchive {
         })
     }
 
+    ///
+    /// Returns the inventory of variables, sorted by task and then by
+    /// qualified name.
+    ///
+    pub fn variables_table(&self) -> Result<Vec<HubrisVariableInfo>> {
+        let mut all: Vec<_> = self
+            .qualified_variables()
+            .map(|(n, v)| (HubrisTask::from(v.goff), n, v))
+            .collect::<_>();
+
+        all.sort();
+
+        all.into_iter()
+            .map(|(task, name, v)| {
+                Ok(HubrisVariableInfo {
+                    module: self.lookup_module(task)?.name.clone(),
+                    name: name.to_string(),
+                    addr: v.addr,
+                    size: v.size,
+                    goff: v.goff,
+                })
+            })
+            .collect()
+    }
+
     pub fn lookup_module(&self, task: HubrisTask) -> Result<&HubrisModule> {
         match self.modules.values().find(|m| m.task == task) {
             Some(module) => Ok(module),
@@ -2015,6 +2150,59 @@ impl HubrisArchive {
         self.task_dump.map(|task| HubrisTask::Task(task.id.into()))
     }
 
+    /// Returns the endianness of the target, as determined by the kernel's
+    /// ELF header (and assumed to be little-endian if unknown).
+    pub fn endianness(&self) -> HubrisEndianness {
+        self.endianness.unwrap_or(HubrisEndianness::Little)
+    }
+
+    ///
+    /// Decodes a 32-bit word from the first four bytes of `bytes`, honoring
+    /// the endianness of the target.
+    ///
+    pub fn read_u32(&self, bytes: &[u8]) -> Result<u32> {
+        self.decode_pod(bytes)
+    }
+
+    ///
+    /// Decodes a plain-old-data value from the beginning of `bytes`, honoring
+    /// the endianness of the target.
+    ///
+    pub fn decode_pod<T: HubrisPod>(&self, bytes: &[u8]) -> Result<T> {
+        match bytes.get(..T::SIZE) {
+            Some(bytes) => Ok(T::decode(bytes, self.endianness())),
+            None => bail!("expected {} bytes, found {}", T::SIZE, bytes.len()),
+        }
+    }
+
+    ///
+    /// Reads a plain-old-data value from the target at the specified address,
+    /// honoring the endianness of the target.
+    ///
+    pub fn read_pod<T: HubrisPod>(
+        &self,
+        core: &mut dyn crate::core::Core,
+        addr: u32,
+    ) -> Result<T> {
+        let mut buf = vec![0u8; T::SIZE];
+        core.read_8(addr, &mut buf)?;
+        self.decode_pod(&buf)
+    }
+
+    ///
+    /// Returns the pattern with which the kernel fills uninitialized stack:
+    /// the pattern declared as `stack-fill` in the kernel's configuration,
+    /// if any, or [`HUBRIS_STACK_FILL`] otherwise.
+    ///
+    pub fn stack_fill(&self) -> u32 {
+        self.manifest.stack_fill.unwrap_or(HUBRIS_STACK_FILL)
+    }
+
+    /// Returns the base and size of the kernel's stack, if it is known.
+    pub fn kernel_stack(&self) -> Option<(u32, u32)> {
+        self.kstack
+    }
+
     pub fn current_task(
         &self,
         core: &mut dyn crate::core::Core,
@@ -2061,7 +2249,7 @@ impl HubrisArchive {
     pub fn ticks(&self, core: &mut dyn crate::core::Core) -> Result<u64> {
         match self.task_dump {
             Some(task) => Ok(task.time),
-            None => core.read_word_64(self.lookup_variable("TICKS")?.addr),
+            None => self.read_pod(core, self.lookup_variable("TICKS")?.addr),
         }
     }
 
@@ -2461,6 +2649,76 @@ impl HubrisArchive {
         Ok(offset as u32)
     }
 
+    ///
+    /// Resolves a path of member accesses and array subscripts (e.g.,
+    /// `.config.thresholds[2]`) relative to the specified type, returning the
+    /// offset of the designated member and its type.  Tuple members can be
+    /// specified by their index (e.g., `.0`).
+    ///
+    pub fn lookup_member_path(
+        &self,
+        goff: HubrisGoff,
+        path: &str,
+    ) -> Result<(usize, HubrisGoff)> {
+        let mut goff = goff;
+        let mut offset = 0;
+        let mut rest = path;
+
+        while !rest.is_empty() {
+            if let Some(r) = rest.strip_prefix('.') {
+                let end = r.find(['.', '[']).unwrap_or(r.len());
+                let field = &r[..end];
+
+                let s = self.lookup_struct(goff).map_err(|_| {
+                    anyhow!("can't access member {} of non-structure", field)
+                })?;
+
+                let m = s
+                    .lookup_member(field)
+                    .or_else(|_| s.lookup_member(&format!("__{}", field)))?;
+
+                if m.bitfield.is_some() {
+                    bail!(
+                        "can't access bitfield member {}; \
+                        read the containing structure instead",
+                        field
+                    );
+                }
+
+                offset += m.offset;
+                goff = m.goff;
+                rest = &r[end..];
+            } else if let Some(r) = rest.strip_prefix('[') {
+                let end = r
+                    .find(']')
+                    .ok_or_else(|| anyhow!("missing ']' in \"{}\"", path))?;
+
+                let index: usize = parse_int::parse(&r[..end])
+                    .with_context(|| format!("bad index in \"{}\"", path))?;
+
+                let array = self.lookup_array(goff).map_err(|_| {
+                    anyhow!("can't index non-array with [{}]", index)
+                })?;
+
+                if index >= array.count {
+                    bail!(
+                        "index {} out of range: array has {} elements",
+                        index,
+                        array.count
+                    );
+                }
+
+                offset += index * self.typesize(array.goff)?;
+                goff = array.goff;
+                rest = &r[end + 1..];
+            } else {
+                bail!("malformed member path \"{}\"", path);
+            }
+        }
+
+        Ok((offset, goff))
+    }
+
     //
     // Returns a vector of all region descriptor addresses for all tasks.
     //
@@ -2692,10 +2950,266 @@ impl HubrisArchive {
         Ok(regions)
     }
 
+    ///
+    /// Like [`regions`](Self::regions), but returns only the regions of the
+    /// specified kind.
+    ///
+    pub fn regions_by_kind(
+        &self,
+        core: &mut dyn crate::core::Core,
+        kind: HubrisRegionKind,
+    ) -> Result<BTreeMap<u32, HubrisRegion>> {
+        let regions = self.regions(core)?;
+        Ok(regions.into_iter().filter(|(_, r)| r.kind() == kind).collect())
+    }
+
+    ///
+    /// Returns the region in `regions` (as returned by
+    /// [`regions`](Self::regions)) containing `addr`, where an address is
+    /// considered to be contained by a region if it is above the region's
+    /// base and no greater than its base plus its size.  (This is the
+    /// semantics of a stack pointer:  an initial stack pointer is one past
+    /// the end of the region containing the stack.)  As regions are keyed by
+    /// base address, this is a logarithmic operation:  only the region with
+    /// the greatest base below `addr` is considered.  Note that this differs
+    /// from [`region_at`](Self::region_at) (and from the linear scan that
+    /// this replaced) should regions overlap:  if the address lies beyond
+    /// that region but within an overlapping region with a lower base, no
+    /// region is returned.
+    ///
+    pub fn region_containing(
+        regions: &BTreeMap<u32, HubrisRegion>,
+        addr: u32,
+    ) -> Option<&HubrisRegion> {
+        let (_, region) = regions.range(..addr).next_back()?;
+
+        if addr as u64 <= region.base as u64 + region.size as u64 {
+            Some(region)
+        } else {
+            None
+        }
+    }
+
+    ///
+    /// Returns the region in `regions` that contains `addr` -- that is, the
+    /// region whose base is no greater than `addr` and that ends after it.
+    /// (Contrast with [`region_containing`](Self::region_containing), which
+    /// has the semantics of a stack pointer.)  Should regions overlap, the
+    /// containing region with the greatest base is returned.
+    ///
+    pub fn region_at(
+        regions: &BTreeMap<u32, HubrisRegion>,
+        addr: u32,
+    ) -> Option<&HubrisRegion> {
+        regions.range(..=addr).rev().map(|(_, region)| region).find(|region| {
+            (addr as u64) < region.base as u64 + region.size as u64
+        })
+    }
+
+    ///
+    /// Returns the first address in `[addr, addr + len)` that isn't mapped
+    /// into the specified task by any of `regions`, or `None` if the task can
+    /// see the entire range.  The range may span adjacent regions.
+    ///
+    pub fn unmapped_in_task(
+        regions: &BTreeMap<u32, HubrisRegion>,
+        task: HubrisTask,
+        addr: u32,
+        len: usize,
+    ) -> Option<u32> {
+        let end = addr as u64 + len as u64;
+        let mut addr = addr as u64;
+
+        while addr < end {
+            let region = regions
+                .range(..=addr as u32)
+                .rev()
+                .map(|(_, region)| region)
+                .filter(|region| region.tasks.contains(&task))
+                .find(|region| addr < region.base as u64 + region.size as u64);
+
+            match region {
+                Some(region) => addr = region.base as u64 + region.size as u64,
+                None => return Some(addr as u32),
+            }
+        }
+
+        None
+    }
+
+    ///
+    /// Reads memory at the specified address as seen by the specified task.
+    /// Hubris tasks share a single address space (the MPU restricts access
+    /// but doesn't translate addresses), so the translation is the identity
+    /// -- but the memory must be mapped into the task, which we check against
+    /// the task's regions.
+    ///
+    pub fn read_8_in_task(
+        &self,
+        core: &mut dyn crate::core::Core,
+        task: HubrisTask,
+        addr: u32,
+        data: &mut [u8],
+    ) -> Result<()> {
+        let regions = self
+            .regions(core)
+            .context("no memory map with which to check task addresses")?;
+
+        if let Some(unmapped) =
+            Self::unmapped_in_task(&regions, task, addr, data.len())
+        {
+            let name = self.lookup_module(task)?.name.as_str();
+            bail!("0x{unmapped:x} is not mapped into {name}");
+        }
+
+        core.read_8(addr, data)
+    }
+
+    ///
+    /// Reads the initial stack pointer of the specified task from its task
+    /// descriptor.
+    ///
+    pub fn task_initial_stack(
+        &self,
+        core: &mut dyn crate::core::Core,
+        t: HubrisTask,
+    ) -> Result<u32> {
+        let ndx = match t {
+            HubrisTask::Task(ndx) => ndx,
+            _ => {
+                bail!("must provide a user task")
+            }
+        };
+
+        let (base, _) = self.task_table(core)?;
+        let task = self.lookup_struct_byname("Task")?;
+        let taskdesc = self.lookup_struct_byname("TaskDesc")?;
+        let descriptor = task.lookup_member("descriptor")?.offset as u32;
+        let initial = taskdesc.lookup_member("initial_stack")?.offset as u32;
+
+        let daddr = self.read_pod::<u32>(
+            core,
+            base + ndx * task.size as u32 + descriptor,
+        )?;
+
+        self.read_pod::<u32>(core, daddr + initial)
+    }
+
+    ///
+    /// Reads and decodes the kernel's `Task` structure (state, priority,
+    /// generation, timer, etc.) for the task at the specified index in the
+    /// task table.
+    ///
+    pub fn read_task(
+        &self,
+        core: &mut dyn crate::core::Core,
+        index: u32,
+    ) -> Result<HubrisValue> {
+        let (base, count) = self.task_table(core)?;
+
+        if index >= count {
+            bail!("task index {} exceeds task count ({})", index, count);
+        }
+
+        let task = self.lookup_struct_byname("Task")?;
+        let mut buf = vec![0u8; task.size];
+
+        core.read_8(base + index * task.size as u32, &mut buf)
+            .with_context(|| format!("failed to read task {}", index))?;
+
+        self.read_value(&buf, task.goff)
+    }
+
+    ///
+    /// Returns the region in `regions` that contains `addr` (as with
+    /// [`region_at`](Self::region_at)), along with the task that owns it.
+    /// Addresses outside of any region -- or in a region shared by more than
+    /// one task -- have no owner.
+    ///
+    pub fn region_owner(
+        regions: &BTreeMap<u32, HubrisRegion>,
+        addr: u32,
+    ) -> Option<(&HubrisRegion, HubrisTask)> {
+        let region = Self::region_at(regions, addr)?;
+
+        match region.tasks[..] {
+            [task] => Some((region, task)),
+            _ => None,
+        }
+    }
+
+    ///
+    /// Determines the task that owns the memory at `addr` (as given by
+    /// `regions`, as returned by [`regions`](Self::regions)), and whether
+    /// that memory is the task's stack.  A task's stack is the region that
+    /// contains its initial stack pointer; the kernel's stack is as found in
+    /// the archive.  Addresses outside of any region -- or in a region shared
+    /// by more than one task -- have no owner.
+    ///
+    pub fn task_for_address(
+        &self,
+        core: &mut dyn crate::core::Core,
+        regions: &BTreeMap<u32, HubrisRegion>,
+        addr: u32,
+    ) -> Result<Option<(HubrisTask, HubrisTaskMemory)>> {
+        let Some((region, task)) = Self::region_owner(regions, addr) else {
+            return Ok(None);
+        };
+
+        let stack = match task {
+            HubrisTask::Kernel => self.kstack.map_or(false, |(base, size)| {
+                addr >= base && (addr as u64) < base as u64 + size as u64
+            }),
+            HubrisTask::Task(_) => {
+                let initial = self.task_initial_stack(core, task)?;
+
+                Self::region_containing(regions, initial)
+                    .map_or(false, |r| r.base == region.base)
+            }
+        };
+
+        let memory = match stack {
+            true => HubrisTaskMemory::Stack,
+            false => HubrisTaskMemory::Data,
+        };
+
+        Ok(Some((task, memory)))
+    }
+
     pub fn dump_registers(&self) -> HashMap<ARMRegister, u32> {
         self.registers.clone()
     }
 
+    ///
+    /// Reads the generation of the specified task, which is incremented each
+    /// time that the task is restarted.
+    ///
+    pub fn task_generation(
+        &self,
+        core: &mut dyn crate::core::Core,
+        t: HubrisTask,
+    ) -> Result<u32> {
+        let ndx = match t {
+            HubrisTask::Task(ndx) => ndx,
+            _ => {
+                bail!("must provide a user task")
+            }
+        };
+
+        let (base, _) = self.task_table(core)?;
+        let task = self.lookup_struct_byname("Task")?;
+        let member = task.lookup_member("generation")?;
+        let size = self.typesize(member.goff)?;
+        let addr = base + (ndx * task.size as u32) + member.offset as u32;
+
+        Ok(match size {
+            1 => self.read_pod::<u8>(core, addr)?.into(),
+            2 => self.read_pod::<u16>(core, addr)?.into(),
+            4 => self.read_pod::<u32>(core, addr)?,
+            _ => bail!("generation is unexpectedly {} bytes", size),
+        })
+    }
+
     pub fn registers(
         &self,
         core: &mut dyn crate::core::Core,
@@ -3022,6 +3536,30 @@ impl HubrisArchive {
         Ok(rval)
     }
 
+    ///
+    /// Unwinds the stack of the specified task from its current register
+    /// state, returning its frames (innermost first).  The unwind is limited
+    /// to the region that contains the task's stack pointer.
+    ///
+    pub fn stack_trace(
+        &self,
+        core: &mut dyn crate::core::Core,
+        task: HubrisTask,
+    ) -> Result<Vec<HubrisStackFrame>> {
+        let regs = self.registers(core, task)?;
+        let sp = *regs
+            .get(&ARMRegister::SP)
+            .ok_or_else(|| anyhow!("SP missing from regs map"))?;
+
+        let regions = self.regions(core)?;
+        let region =
+            Self::region_containing(&regions, sp).ok_or_else(|| {
+                anyhow!("could not find memory region containing sp 0x{:x}", sp)
+            })?;
+
+        self.stack(core, task, region.base + region.size, &regs)
+    }
+
     pub fn typesize(&self, goff: HubrisGoff) -> Result<usize> {
         if let Some(v) = self.structs.get(&goff) {
             return Ok(v.size);
@@ -3090,28 +3628,69 @@ impl HubrisArchive {
         fmt: HubrisPrintFormat,
     ) -> Result<String> {
         use crate::reflect::Format;
-        let mut rval = vec![];
-        crate::reflect::load_value(
-            self,
-            buf,
-            self.lookup_type(goff).unwrap(),
-            0,
-        )?
-        .format(self, fmt, &mut rval)?;
 
-        let out = std::str::from_utf8(&rval)?.to_owned();
-        Ok(out)
-    }
+        let mut rval = vec![];
+        self.read_value(buf, goff)?.format(self, fmt, &mut rval)?;
 
-    pub fn print(&self, buf: &[u8], goff: HubrisGoff) -> Result<String> {
-        self.printfmt(
-            buf,
-            goff,
-            HubrisPrintFormat { hex: true, ..HubrisPrintFormat::default() },
-        )
+        Ok(String::from_utf8(rval)?)
     }
 
-    pub fn explain(
+    ///
+    /// Like [`printfmt`](Self::printfmt), but hands each line of the
+    /// formatted value (without its terminating newline) to `emit` as soon
+    /// as it has been formatted, allowing the caller to display a large
+    /// value as it goes rather than assembling it as a single string.
+    ///
+    pub fn printfmt_lines(
+        &self,
+        buf: &[u8],
+        goff: HubrisGoff,
+        fmt: HubrisPrintFormat,
+        emit: impl FnMut(&str),
+    ) -> Result<()> {
+        use crate::reflect::Format;
+
+        let mut out = HubrisLineWriter::new(emit);
+        self.read_value(buf, goff)?.format(self, fmt, &mut out)?;
+        out.finish();
+
+        Ok(())
+    }
+
+    ///
+    /// Decodes the value of type `goff` in `buf`, returning a structured
+    /// value rather than formatted text.
+    ///
+    pub fn read_value(
+        &self,
+        buf: &[u8],
+        goff: HubrisGoff,
+    ) -> Result<HubrisValue> {
+        crate::reflect::load_value(self, buf, self.lookup_type(goff)?, 0)
+    }
+
+    ///
+    /// Like [`printfmt`](Self::printfmt), but renders the value as JSON.
+    ///
+    pub fn printjson(
+        &self,
+        buf: &[u8],
+        goff: HubrisGoff,
+    ) -> Result<serde_json::Value> {
+        use crate::reflect::Json;
+
+        self.read_value(buf, goff)?.to_json(self)
+    }
+
+    pub fn print(&self, buf: &[u8], goff: HubrisGoff) -> Result<String> {
+        self.printfmt(
+            buf,
+            goff,
+            HubrisPrintFormat { hex: true, ..HubrisPrintFormat::default() },
+        )
+    }
+
+    pub fn explain(
         &self,
         regions: &BTreeMap<u32, HubrisRegion>,
         val: u32,
@@ -3727,6 +4306,13 @@ impl HubrisArchive {
 struct HubrisObjectLoader {
     current: u32,
 
+    // Split DWARF objects found in the archive: file name to contents
+    dwos: Arc<HashMap<String, Vec<u8>>>,
+
+    // Offset added to DWARF section offsets to form goffs, allowing the
+    // units of split DWARF objects to share the object's goff space
+    goff_base: usize,
+
     // image ID
     imageid: Option<(u32, Vec<u8>)>,
 
@@ -3748,6 +4334,12 @@ struct HubrisObjectLoader {
     // loaded regions
     loaded: BTreeMap<u32, HubrisRegion>,
 
+    // kernel stack, as a base and size
+    kstack: Option<(u32, u32)>,
+
+    // endianness of the kernel
+    endianness: Option<HubrisEndianness>,
+
     // app table
     apptable: Option<(u32, Vec<u8>)>,
 
@@ -3817,6 +4409,8 @@ impl HubrisObjectLoader {
     fn new(current: u32) -> Result<Self> {
         Ok(Self {
             current,
+            dwos: Arc::new(HashMap::new()),
+            goff_base: 0,
             apptable: None,
             imageid: None,
             arrays: HashMap::new(),
@@ -3835,6 +4429,8 @@ impl HubrisObjectLoader {
             inlined: BTreeMap::new(),
             instrs: HashMap::new(),
             loaded: BTreeMap::new(),
+            kstack: None,
+            endianness: None,
             ptrtypes: HashMap::new(),
             unions: HashMap::new(),
             namespaces: Namespaces::new(),
@@ -3878,6 +4474,14 @@ impl HubrisObjectLoader {
 
         let arm = elf.header.e_machine == goblin::elf::header::EM_ARM;
 
+        if task == HubrisTask::Kernel {
+            self.endianness = Some(if elf.little_endian {
+                HubrisEndianness::Little
+            } else {
+                HubrisEndianness::Big
+            });
+        }
+
         if !arm {
             bail!("{} not an ARM ELF object", object);
         }
@@ -4078,6 +4682,7 @@ impl HubrisObjectLoader {
                     tasks: vec![task],
                 };
 
+                self.kstack = Some((region.base, region.size));
                 self.loaded.insert(region.base, region);
             }
         }
@@ -4197,24 +4802,7 @@ impl HubrisObjectLoader {
         // RAM -- since we've already loaded the Elf file, this can't fail.
         let dwarf = gimli::Dwarf::<&[u8]>::load(
             // Load the normal DWARF section(s) from our Elf image.
-            |id| {
-                let sec_result = elf.section_headers.iter().find(|sh| {
-                    if let Some(Ok(name)) = elf.shdr_strtab.get(sh.sh_name) {
-                        name == id.name()
-                    } else {
-                        false
-                    }
-                });
-                if let Some(sec) = sec_result {
-                    let offset = sec.sh_offset as usize;
-                    let size = sec.sh_size as usize;
-                    buffer.get(offset..offset + size).ok_or_else(|| {
-                        anyhow!("bad offset/size for ELF section {}", id.name())
-                    })
-                } else {
-                    Ok(&[])
-                }
-            },
+            |id| elf_section(elf, buffer, id.name()),
             // We don't have a supplemental object file.
             |_| Ok(&[]),
         )?;
@@ -4224,167 +4812,297 @@ impl HubrisObjectLoader {
             gimli::EndianSlice::new(section, gimli::LittleEndian)
         });
 
+        //
+        // Split debug objects have section offsets of their own, so we place
+        // their goffs beyond the end of our .debug_info section.
+        //
+        let info = elf_section(elf, buffer, ".debug_info")?;
+        let mut goff_base = info.len();
+        let mut ids = dwarf_header_dwo_ids(info).into_iter();
+
         // Iterate over the compilation units.
         let mut iter = dwarf.units();
         while let Some(header) = iter.next()? {
             let unit = dwarf.unit(header)?;
-            let mut entries = unit.entries();
-            let mut depth = 0;
-            let mut stack: Vec<HubrisGoff> = vec![];
+            let id = ids.next().flatten();
 
-            let mut array = None;
-            let mut ns = vec![];
+            //
+            // If this is the skeleton of a unit compiled with split DWARF,
+            // its contents are in a split debug object.
+            //
+            if let Some(dwo) = dwarf_dwo_name(&dwarf, &unit)? {
+                let id = dwarf_dwo_id(&unit, id)?;
+                goff_base += self
+                    .load_dwo_dwarf(buffer, elf, &unit, id, &dwo, goff_base)?;
+            }
 
-            while let Some((delta, entry)) = entries.next_dfs()? {
-                depth += delta;
+            self.load_unit_dwarf(&dwarf, &unit)?;
+        }
 
-                //
-                // See if our depth has become shallower than our namespace,
-                // trimming it until it fits.
-                //
-                while let Some((_, d)) = ns.last() {
-                    if depth > *d {
-                        break;
+        Ok(())
+    }
+
+    ///
+    /// Loads the split debug object (.dwo file) named by a skeleton unit,
+    /// which we look for first in the archive and then relative to the
+    /// unit's compilation directory.  If the skeleton has a DWO ID, the
+    /// object must have the same one; an object with a different DWO ID is
+    /// stale, and is ignored.  The goffs of its contents start at
+    /// `goff_base`; we return the size of its .debug_info section.
+    ///
+    fn load_dwo_dwarf(
+        &mut self,
+        buffer: &[u8],
+        elf: &goblin::elf::Elf,
+        skeleton: &gimli::Unit<gimli::EndianSlice<gimli::LittleEndian>>,
+        id: Option<u64>,
+        name: &str,
+        goff_base: usize,
+    ) -> Result<usize> {
+        let dwos = self.dwos.clone();
+
+        let contents = match dwos.get(name.rsplit('/').next().unwrap()) {
+            Some(contents) => Cow::Borrowed(&contents[..]),
+            None => {
+                let path = match &skeleton.comp_dir {
+                    Some(dir) => Path::new(&*dir.to_string_lossy()).join(name),
+                    None => Path::new(name).to_path_buf(),
+                };
+
+                match fs::read(&path) {
+                    Ok(contents) => Cow::Owned(contents),
+                    Err(err) => {
+                        warn!(
+                            "could not load split DWARF object {}: {}",
+                            path.display(),
+                            err
+                        );
+                        return Ok(0);
                     }
+                }
+            }
+        };
 
-                    ns.pop();
+        let dwo = Elf::parse(&contents).map_err(|e| {
+            anyhow!("unrecognized split DWARF object: {}: {}", name, e)
+        })?;
+
+        //
+        // A split debug object has its own copies of most sections (with a
+        // ".dwo" suffix) -- but addresses are in our own address table.
+        //
+        let dwarf = gimli::Dwarf::<&[u8]>::load(
+            |id| match id {
+                gimli::SectionId::DebugAddr => {
+                    elf_section(elf, buffer, id.name())
+                }
+                _ => {
+                    elf_section(&dwo, &contents, &format!("{}.dwo", id.name()))
                 }
+            },
+            |_| Ok(&[]),
+        )?;
 
-                let goff = self.dwarf_goff(&unit, entry);
-                self.dwarf_fileline(&dwarf, &unit, entry)?;
+        let dwarf = dwarf.borrow(|section| {
+            gimli::EndianSlice::new(section, gimli::LittleEndian)
+        });
 
-                if depth as usize >= stack.len() {
-                    stack.push(goff);
-                } else {
-                    stack[depth as usize] = goff;
+        let info = elf_section(&dwo, &contents, ".debug_info.dwo")?;
+        let mut ids = dwarf_header_dwo_ids(info).into_iter();
+        let mut units = vec![];
+
+        let mut iter = dwarf.units();
+        while let Some(header) = iter.next()? {
+            let mut unit = dwarf.unit(header)?;
+            let found = dwarf_dwo_id(&unit, ids.next().flatten())?;
+
+            if let (Some(id), Some(found)) = (id, found) {
+                if id != found {
+                    warn!(
+                        "ignoring stale split DWARF object {}: DWO ID is \
+                        0x{:016x}, expected 0x{:016x}",
+                        name, found, id
+                    );
+                    return Ok(0);
                 }
+            }
 
-                match entry.tag() {
-                    gimli::constants::DW_TAG_namespace => {
-                        self.dwarf_namespace(&dwarf, entry, depth, &mut ns)?;
-                    }
+            dwarf_split_unit(&mut unit, skeleton);
+            units.push(unit);
+        }
 
-                    gimli::constants::DW_TAG_inlined_subroutine => {
-                        self.dwarf_inlined(&dwarf, &unit, entry, depth)?;
-                    }
+        self.goff_base = goff_base;
 
-                    gimli::constants::DW_TAG_subprogram => {
-                        self.dwarf_subprogram(&dwarf, &unit, entry)?;
-                    }
+        for unit in &units {
+            self.load_unit_dwarf(&dwarf, unit)?;
+        }
 
-                    gimli::constants::DW_TAG_variable => {
-                        self.dwarf_variable(&dwarf, &unit, entry)?;
-                    }
+        self.goff_base = 0;
 
-                    gimli::constants::DW_TAG_structure_type => {
-                        let id = ns.last().map(|(id, _)| *id);
-                        self.dwarf_struct(&dwarf, &unit, entry, id)?;
+        Ok(info.len())
+    }
 
-                        //
-                        // We want to also treat a structure as a namespace
-                        // to allow for the disambiguation of embedded
-                        // structures.
-                        //
-                        self.dwarf_namespace(&dwarf, entry, depth, &mut ns)?;
-                    }
+    fn load_unit_dwarf(
+        &mut self,
+        dwarf: &gimli::Dwarf<gimli::EndianSlice<gimli::LittleEndian>>,
+        unit: &gimli::Unit<gimli::EndianSlice<gimli::LittleEndian>>,
+    ) -> Result<()> {
+        let mut entries = unit.entries();
+        let mut depth = 0;
+        let mut stack: Vec<HubrisGoff> = vec![];
 
-                    gimli::constants::DW_TAG_base_type => {
-                        self.dwarf_basetype(&dwarf, &unit, entry)?;
-                    }
+        let mut array = None;
+        let mut ns = vec![];
 
-                    gimli::constants::DW_TAG_pointer_type => {
-                        self.dwarf_ptrtype(&dwarf, &unit, entry)?;
-                    }
+        while let Some((delta, entry)) = entries.next_dfs()? {
+            depth += delta;
 
-                    gimli::constants::DW_TAG_array_type => {
-                        let mut attrs = entry.attrs();
+            //
+            // See if our depth has become shallower than our namespace,
+            // trimming it until it fits.
+            //
+            while let Some((_, d)) = ns.last() {
+                if depth > *d {
+                    break;
+                }
 
-                        while let Some(attr) = attrs.next()? {
-                            if attr.name() != gimli::constants::DW_AT_type {
-                                continue;
-                            }
+                ns.pop();
+            }
 
-                            array = self.dwarf_value_goff(&unit, &attr.value());
-                            break;
-                        }
-                    }
+            let goff = self.dwarf_goff(unit, entry);
+            self.dwarf_fileline(dwarf, unit, entry)?;
+
+            if depth as usize >= stack.len() {
+                stack.push(goff);
+            } else {
+                stack[depth as usize] = goff;
+            }
+
+            match entry.tag() {
+                gimli::constants::DW_TAG_namespace => {
+                    self.dwarf_namespace(dwarf, unit, entry, depth, &mut ns)?;
+                }
+
+                gimli::constants::DW_TAG_inlined_subroutine => {
+                    self.dwarf_inlined(dwarf, unit, entry, depth)?;
+                }
+
+                gimli::constants::DW_TAG_subprogram => {
+                    self.dwarf_subprogram(dwarf, unit, entry)?;
+                }
+
+                gimli::constants::DW_TAG_variable => {
+                    self.dwarf_variable(dwarf, unit, entry)?;
+                }
+
+                gimli::constants::DW_TAG_structure_type => {
+                    let id = ns.last().map(|(id, _)| *id);
+                    self.dwarf_struct(dwarf, unit, entry, id)?;
+
+                    //
+                    // We want to also treat a structure as a namespace
+                    // to allow for the disambiguation of embedded
+                    // structures.
+                    //
+                    self.dwarf_namespace(dwarf, unit, entry, depth, &mut ns)?;
+                }
+
+                gimli::constants::DW_TAG_base_type => {
+                    self.dwarf_basetype(dwarf, unit, entry)?;
+                }
 
-                    gimli::constants::DW_TAG_subrange_type => {
-                        if depth == 0 {
-                            bail!("no array for subrange {}", goff);
+                gimli::constants::DW_TAG_pointer_type => {
+                    self.dwarf_ptrtype(dwarf, unit, entry)?;
+                }
+
+                gimli::constants::DW_TAG_array_type => {
+                    let mut attrs = entry.attrs();
+
+                    while let Some(attr) = attrs.next()? {
+                        if attr.name() != gimli::constants::DW_AT_type {
+                            continue;
                         }
 
-                        let parent = stack[depth as usize - 1];
-                        self.dwarf_array(&unit, entry, parent, array)?;
-                        array = None;
+                        array = self.dwarf_value_goff(unit, &attr.value());
+                        break;
                     }
+                }
 
-                    gimli::constants::DW_TAG_enumeration_type => {
-                        let id = ns.last().map(|(id, _)| *id);
-                        self.dwarf_const_enum(&dwarf, &unit, entry, goff, id)?;
+                gimli::constants::DW_TAG_subrange_type => {
+                    if depth == 0 {
+                        bail!("no array for subrange {}", goff);
                     }
 
-                    gimli::constants::DW_TAG_enumerator => {
-                        let parent = stack[depth as usize - 1];
+                    let parent = stack[depth as usize - 1];
+                    self.dwarf_array(unit, entry, parent, array)?;
+                    array = None;
+                }
 
-                        self.dwarf_enum_variant(&dwarf, &unit, entry, parent)?;
-                    }
+                gimli::constants::DW_TAG_enumeration_type => {
+                    let id = ns.last().map(|(id, _)| *id);
+                    self.dwarf_const_enum(dwarf, unit, entry, goff, id)?;
+                }
 
-                    gimli::constants::DW_TAG_variant_part => {
-                        if depth == 0 {
-                            bail!("no enum for variant {}", goff);
-                        }
+                gimli::constants::DW_TAG_enumerator => {
+                    let parent = stack[depth as usize - 1];
 
-                        let parent = stack[depth as usize - 1];
-                        self.dwarf_enum(&unit, entry, parent)?;
+                    self.dwarf_enum_variant(dwarf, unit, entry, parent)?;
+                }
 
-                        //
-                        // The discriminant is a (grand)child member; we need
-                        // to duplicate our parent's goff so our child can
-                        // find it.
-                        //
-                        stack[depth as usize] = parent;
+                gimli::constants::DW_TAG_variant_part => {
+                    if depth == 0 {
+                        bail!("no enum for variant {}", goff);
                     }
 
-                    gimli::constants::DW_TAG_variant => {
-                        if depth == 0 {
-                            bail!("no enum for variant {}", goff);
-                        }
+                    let parent = stack[depth as usize - 1];
+                    self.dwarf_enum(unit, entry, parent)?;
 
-                        let parent = stack[depth as usize - 1];
-                        self.dwarf_variant(&unit, entry, parent)?;
+                    //
+                    // The discriminant is a (grand)child member; we need
+                    // to duplicate our parent's goff so our child can
+                    // find it.
+                    //
+                    stack[depth as usize] = parent;
+                }
 
-                        //
-                        // Our discriminant is still below us as a child
-                        // member, so, as in the DW_TAG_variant_part case
-                        // (which is our parent), we need to copy our parent
-                        // down.
-                        //
-                        stack[depth as usize] = parent;
+                gimli::constants::DW_TAG_variant => {
+                    if depth == 0 {
+                        bail!("no enum for variant {}", goff);
                     }
 
-                    gimli::constants::DW_TAG_member => {
-                        if depth == 0 {
-                            bail!("no parent for member {}", goff);
-                        }
+                    let parent = stack[depth as usize - 1];
+                    self.dwarf_variant(unit, entry, parent)?;
 
-                        let parent = stack[depth as usize - 1];
-                        self.dwarf_member(&dwarf, &unit, entry, parent)?;
-                    }
+                    //
+                    // Our discriminant is still below us as a child
+                    // member, so, as in the DW_TAG_variant_part case
+                    // (which is our parent), we need to copy our parent
+                    // down.
+                    //
+                    stack[depth as usize] = parent;
+                }
 
-                    gimli::constants::DW_TAG_union_type => {
-                        self.dwarf_union(&dwarf, &unit, entry)?;
+                gimli::constants::DW_TAG_member => {
+                    if depth == 0 {
+                        bail!("no parent for member {}", goff);
                     }
 
-                    _ => {}
+                    let parent = stack[depth as usize - 1];
+                    self.dwarf_member(dwarf, unit, entry, parent)?;
+                }
+
+                gimli::constants::DW_TAG_union_type => {
+                    self.dwarf_union(dwarf, unit, entry)?;
                 }
-            }
 
-            if let Some(array) = array {
-                bail!("missing subrange for array {}", array);
+                _ => {}
             }
         }
 
+        if let Some(array) = array {
+            bail!("missing subrange for array {}", array);
+        }
+
         Ok(())
     }
 
@@ -4573,7 +5291,7 @@ impl HubrisObjectLoader {
             gimli::UnitSectionOffset::DebugTypesOffset(o) => o.0,
         };
 
-        HubrisGoff { object: self.current, goff }
+        HubrisGoff { object: self.current, goff: self.goff_base + goff }
     }
 
     fn dwarf_union<R: gimli::Reader<Offset = usize>>(
@@ -4593,7 +5311,7 @@ impl HubrisObjectLoader {
         while let Some(attr) = attrs.next()? {
             match attr.name() {
                 gimli::constants::DW_AT_name => {
-                    name = dwarf_name(dwarf, attr.value());
+                    name = dwarf_name(dwarf, unit, attr.value());
                 }
 
                 gimli::constants::DW_AT_byte_size => {
@@ -4634,12 +5352,14 @@ impl HubrisObjectLoader {
         let mut name = None;
         let mut offset = None;
         let mut goff = None;
+        let mut bitoffset = None;
+        let mut bitsize = None;
         let member = self.dwarf_goff(unit, entry);
 
         while let Some(attr) = attrs.next()? {
             match attr.name() {
                 gimli::constants::DW_AT_name => {
-                    name = dwarf_name(dwarf, attr.value());
+                    name = dwarf_name(dwarf, unit, attr.value());
                 }
 
                 gimli::constants::DW_AT_data_member_location => {
@@ -4648,6 +5368,18 @@ impl HubrisObjectLoader {
                     }
                 }
 
+                gimli::constants::DW_AT_data_bit_offset => {
+                    if let gimli::AttributeValue::Udata(value) = attr.value() {
+                        bitoffset = Some(value as usize)
+                    }
+                }
+
+                gimli::constants::DW_AT_bit_size => {
+                    if let gimli::AttributeValue::Udata(value) = attr.value() {
+                        bitsize = Some(value as usize)
+                    }
+                }
+
                 gimli::constants::DW_AT_type => {
                     goff = self.dwarf_value_goff(unit, &attr.value());
                 }
@@ -4656,12 +5388,27 @@ impl HubrisObjectLoader {
             }
         }
 
+        //
+        // A bitfield has a bit size and (usually) a bit offset from the start
+        // of the structure rather than a byte offset; we split the latter
+        // into a byte offset and a bit offset within it.
+        //
+        let bitfield = match (bitsize, bitoffset) {
+            (Some(size), Some(bits)) => {
+                offset = Some(offset.unwrap_or(0) + bits / 8);
+                Some(HubrisBitfield { offset: bits % 8, size })
+            }
+            (Some(size), None) => Some(HubrisBitfield { offset: 0, size }),
+            (None, _) => None,
+        };
+
         if let Some(pstruct) = self.structs.get_mut(&parent) {
             if let (Some(n), Some(offs), Some(g)) = (name, offset, goff) {
                 pstruct.members.push(HubrisStructMember {
                     name: n.to_string(),
                     offset: offs,
                     goff: g,
+                    bitfield,
                 });
             } else {
                 bail!("member {} is incomplete", member);
@@ -4749,7 +5496,7 @@ impl HubrisObjectLoader {
             }
         };
 
-        Some(HubrisGoff { object: self.current, goff })
+        Some(HubrisGoff { object: self.current, goff: self.goff_base + goff })
     }
 
     fn dwarf_variant<R: gimli::Reader<Offset = usize>>(
@@ -4855,7 +5602,7 @@ impl HubrisObjectLoader {
         while let Some(attr) = attrs.next()? {
             match attr.name() {
                 gimli::constants::DW_AT_name => {
-                    name = dwarf_name(dwarf, attr.value());
+                    name = dwarf_name(dwarf, unit, attr.value());
                 }
 
                 gimli::constants::DW_AT_const_value => {
@@ -4916,7 +5663,7 @@ impl HubrisObjectLoader {
         while let Some(attr) = attrs.next()? {
             match attr.name() {
                 gimli::constants::DW_AT_name => {
-                    name = dwarf_name(dwarf, attr.value());
+                    name = dwarf_name(dwarf, unit, attr.value());
                 }
 
                 gimli::constants::DW_AT_type => {
@@ -5004,7 +5751,7 @@ impl HubrisObjectLoader {
         while let Some(attr) = attrs.next()? {
             match attr.name() {
                 gimli::constants::DW_AT_name => {
-                    name = dwarf_name(dwarf, attr.value());
+                    name = dwarf_name(dwarf, unit, attr.value());
                 }
 
                 gimli::constants::DW_AT_byte_size => {
@@ -5070,7 +5817,7 @@ impl HubrisObjectLoader {
                     underlying = self.dwarf_value_goff(unit, &attr.value());
                 }
                 gimli::constants::DW_AT_name => {
-                    name = dwarf_name(dwarf, attr.value());
+                    name = dwarf_name(dwarf, unit, attr.value());
                 }
                 _ => {}
             }
@@ -5085,9 +5832,10 @@ impl HubrisObjectLoader {
         Ok(())
     }
 
-    fn dwarf_namespace(
+    fn dwarf_namespace<R: gimli::Reader<Offset = usize>>(
         &mut self,
         dwarf: &gimli::Dwarf<gimli::EndianSlice<gimli::LittleEndian>>,
+        unit: &gimli::Unit<R>,
         entry: &gimli::DebuggingInformationEntry<
             gimli::EndianSlice<gimli::LittleEndian>,
             usize,
@@ -5103,7 +5851,7 @@ impl HubrisObjectLoader {
         //
         while let Some(attr) = attrs.next()? {
             if attr.name() == gimli::constants::DW_AT_name {
-                if let Some(name) = dwarf_name(dwarf, attr.value()) {
+                if let Some(name) = dwarf_name(dwarf, unit, attr.value()) {
                     namespace.push((
                         self.namespaces.allocate(
                             name,
@@ -5213,7 +5961,7 @@ impl HubrisObjectLoader {
         while let Some(attr) = attrs.next()? {
             match attr.name() {
                 gimli::constants::DW_AT_name => {
-                    name = dwarf_name(dwarf, attr.value());
+                    name = dwarf_name(dwarf, unit, attr.value());
                 }
 
                 gimli::constants::DW_AT_byte_size => {
@@ -5369,10 +6117,10 @@ impl HubrisObjectLoader {
                     len = Some(value);
                 }
                 (gimli::constants::DW_AT_linkage_name, _) => {
-                    linkage_name = dwarf_name(dwarf, attr.value());
+                    linkage_name = dwarf_name(dwarf, unit, attr.value());
                 }
                 (gimli::constants::DW_AT_name, _) => {
-                    name = dwarf_name(dwarf, attr.value());
+                    name = dwarf_name(dwarf, unit, attr.value());
                 }
                 _ => {}
             }
@@ -5429,7 +6177,7 @@ impl HubrisObjectLoader {
         'attrloop: while let Some(attr) = attrs.next()? {
             match attr.name() {
                 gimli::constants::DW_AT_name => {
-                    name = dwarf_name(dwarf, attr.value());
+                    name = dwarf_name(dwarf, unit, attr.value());
                 }
 
                 gimli::constants::DW_AT_external => {
@@ -5439,7 +6187,7 @@ impl HubrisObjectLoader {
                 }
 
                 gimli::constants::DW_AT_linkage_name => {
-                    linkage_name = dwarf_name(dwarf, attr.value());
+                    linkage_name = dwarf_name(dwarf, unit, attr.value());
                 }
 
                 gimli::constants::DW_AT_type => {
@@ -5465,6 +6213,32 @@ impl HubrisObjectLoader {
                         let eval_result = loop {
                             match result {
                                 gimli::EvaluationResult::Complete => break Some(eval.result()),
+                                gimli::EvaluationResult::RequiresIndexedAddress {
+                                    index, ..
+                                } => {
+                                    //
+                                    // Split DWARF refers to addresses by
+                                    // index into the object's address table.
+                                    //
+                                    let addr = dwarf.debug_addr.get_address(
+                                        unit.encoding().address_size,
+                                        unit.addr_base,
+                                        index,
+                                    );
+
+                                    result = match addr.and_then(|a| {
+                                        eval.resume_with_indexed_address(a)
+                                    }) {
+                                        Ok(r) => r,
+                                        Err(e) => {
+                                            warn!(
+                                                "AT_location failed \
+                                                on indexed address: {}", e
+                                            );
+                                            break None;
+                                        }
+                                    };
+                                }
                                 gimli::EvaluationResult::RequiresRelocatedAddress(a) => {
                                     result = match eval.resume_with_relocated_address(a) {
                                         Ok(r) => r,
@@ -5646,6 +6420,18 @@ pub enum HubrisTask {
     Task(u32),
 }
 
+///
+/// The kind of task memory that contains an address, as determined by
+/// [`HubrisArchive::task_for_address`].
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HubrisTaskMemory {
+    /// The task's stack
+    Stack,
+    /// Any other memory belonging to the task (data, text or device)
+    Data,
+}
+
 impl HubrisTask {
     pub fn id(&self) -> String {
         match self {
@@ -5741,6 +6527,18 @@ pub struct HubrisStructMember {
     pub offset: usize,
     pub name: String,
     pub goff: HubrisGoff,
+    pub bitfield: Option<HubrisBitfield>,
+}
+
+///
+/// The bits occupied by a bitfield member, relative to the member's (byte)
+/// offset.  Bits are numbered from the least significant bit of the first
+/// byte.
+///
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HubrisBitfield {
+    pub offset: usize,
+    pub size: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -5812,6 +6610,25 @@ pub struct HubrisVariable {
     pub size: usize,
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub struct HubrisVariableInfo {
+    /// Name of the module containing the variable
+    pub module: String,
+
+    /// Qualified name of the variable
+    pub name: String,
+
+    /// Address of the variable
+    pub addr: u32,
+
+    /// Size of the variable, in bytes
+    pub size: usize,
+
+    /// Type of the variable
+    #[serde(skip)]
+    pub goff: HubrisGoff,
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct HubrisArray {
     pub goff: HubrisGoff,
@@ -5856,6 +6673,30 @@ pub struct HubrisRegion {
     pub tasks: Vec<HubrisTask>,
 }
 
+/// The kind of memory that a region describes.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HubrisRegionKind {
+    /// Writable memory (e.g., data, stacks and DMA buffers)
+    Ram,
+    /// Memory that isn't writable (e.g., text and read-only data in flash)
+    Flash,
+    /// Memory-mapped peripherals
+    Device,
+}
+
+impl HubrisRegion {
+    /// Returns the kind of memory that this region describes.
+    pub fn kind(&self) -> HubrisRegionKind {
+        if self.attr.device {
+            HubrisRegionKind::Device
+        } else if self.attr.write {
+            HubrisRegionKind::Ram
+        } else {
+            HubrisRegionKind::Flash
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct HubrisEnumVariant {
     pub name: String,
@@ -6480,16 +7321,75 @@ impl HubrisModule {
     }
 }
 
+///
+/// A decoded value, as returned by [`HubrisArchive::read_value`].
+///
+pub type HubrisValue = crate::reflect::Value;
+
+///
+/// An [`std::io::Write`] that hands each line written to it (without its
+/// terminating newline) to a callback as soon as the line is complete.  The
+/// final line -- which is empty if the output ends with a newline -- is handed
+/// to the callback by [`finish`](Self::finish).
+///
+pub struct HubrisLineWriter<F: FnMut(&str)> {
+    line: Vec<u8>,
+    emit: F,
+}
+
+impl<F: FnMut(&str)> HubrisLineWriter<F> {
+    pub fn new(emit: F) -> Self {
+        Self { line: vec![], emit }
+    }
+
+    pub fn finish(mut self) {
+        (self.emit)(&String::from_utf8_lossy(&self.line));
+    }
+}
+
+impl<F: FnMut(&str)> std::io::Write for HubrisLineWriter<F> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut rest = buf;
+
+        while let Some(nl) = rest.iter().position(|&b| b == b'\n') {
+            self.line.extend_from_slice(&rest[..nl]);
+            (self.emit)(&String::from_utf8_lossy(&self.line));
+            self.line.clear();
+            rest = &rest[nl + 1..];
+        }
+
+        self.line.extend_from_slice(rest);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 #[derive(Copy, Clone, Debug, Default)]
-pub struct HubrisPrintFormat {
+pub struct HubrisPrintFormat<'a> {
     pub indent: usize,
+    pub indent_width: Option<usize>,
     pub newline: bool,
     pub hex: bool,
+    pub binary: bool,
     pub no_name: bool,
     pub interpret_as_c_string: bool,
+    pub interpret_as_text: bool,
+    pub skip_default: bool,
+    pub scale: Option<f64>,
+    pub unit: Option<&'a str>,
+    pub short_names: bool,
 }
 
-impl HubrisPrintFormat {
+impl HubrisPrintFormat<'_> {
+    /// The number of spaces by which each nested level is indented (four,
+    /// unless otherwise specified).
+    pub fn indent_width(&self) -> usize {
+        self.indent_width.unwrap_or(4)
+    }
+
     pub fn delim(&self) -> &'static str {
         if self.newline {
             "\n"
@@ -6497,8 +7397,91 @@ impl HubrisPrintFormat {
             " "
         }
     }
+
+    /// Returns the specified type name as it should be displayed:  without
+    /// module paths (e.g., `*const Task` rather than `*const kern::task::Task`)
+    /// if short names have been requested.
+    pub fn type_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        if self.short_names {
+            Cow::Owned(short_type_name(name))
+        } else {
+            Cow::Borrowed(name)
+        }
+    }
+}
+
+///
+/// Removes the module path from each type named within a type name (e.g.,
+/// `Option<NonNull<kern::task::Task>>` becomes `Option<NonNull<Task>>`).
+///
+pub fn short_type_name(name: &str) -> String {
+    let mut out = String::new();
+    let mut segment = String::new();
+    let mut chars = name.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            segment.push(c);
+            continue;
+        }
+
+        //
+        // A path separator following an identifier means that the identifier
+        // is a module; we drop both.
+        //
+        if c == ':' && chars.peek() == Some(&':') && !segment.is_empty() {
+            chars.next();
+            segment.clear();
+            continue;
+        }
+
+        out.push_str(&segment);
+        out.push(c);
+        segment.clear();
+    }
+
+    out.push_str(&segment);
+    out
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HubrisEndianness {
+    Little,
+    Big,
+}
+
+///
+/// A plain-old-data type that can be decoded from the target's memory (via
+/// [`HubrisArchive::read_pod`]) in the target's endianness.
+///
+pub trait HubrisPod: Sized {
+    const SIZE: usize;
+
+    /// Decodes a value from exactly `SIZE` bytes.
+    fn decode(bytes: &[u8], endianness: HubrisEndianness) -> Self;
+}
+
+macro_rules! hubris_pod {
+    ($($t:ty),*) => {
+        $(
+            impl HubrisPod for $t {
+                const SIZE: usize = std::mem::size_of::<$t>();
+
+                fn decode(bytes: &[u8], endianness: HubrisEndianness) -> Self {
+                    let bytes = bytes.try_into().unwrap();
+
+                    match endianness {
+                        HubrisEndianness::Little => <$t>::from_le_bytes(bytes),
+                        HubrisEndianness::Big => <$t>::from_be_bytes(bytes),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+hubris_pod!(u8, u16, u32, u64, i8, i16, i32, i64);
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum HubrisValidate {
     ArchiveMatch,
@@ -6657,23 +7640,665 @@ fn try_scoped<'a>(
     }
 }
 
-fn dwarf_name<'a>(
+fn dwarf_name<'a, R: gimli::Reader<Offset = usize>>(
     dwarf: &'a gimli::Dwarf<gimli::EndianSlice<gimli::LittleEndian>>,
+    unit: &gimli::Unit<R>,
     value: gimli::AttributeValue<gimli::EndianSlice<gimli::LittleEndian>>,
 ) -> Option<&'a str> {
-    match value {
-        gimli::AttributeValue::DebugStrRef(strref) => {
-            let dstring = dwarf.debug_str.get_str(strref).ok()?;
-            let ddstring = str::from_utf8(dstring.slice()).ok()?;
-            Some(ddstring)
+    let strref = match value {
+        gimli::AttributeValue::DebugStrRef(strref) => strref,
+
+        //
+        // Split DWARF refers to strings indirectly, through the unit's
+        // string offsets table.
+        //
+        gimli::AttributeValue::DebugStrOffsetsIndex(index) => dwarf
+            .debug_str_offsets
+            .get_str_offset(
+                unit.encoding().format,
+                unit.str_offsets_base,
+                index,
+            )
+            .ok()?,
+
+        _ => return None,
+    };
+
+    let dstring = dwarf.debug_str.get_str(strref).ok()?;
+    str::from_utf8(dstring.slice()).ok()
+}
+
+///
+/// Returns the contents of the named section of an ELF object -- or an empty
+/// slice if the object has no such section.
+///
+fn elf_section<'a>(
+    elf: &goblin::elf::Elf,
+    buffer: &'a [u8],
+    name: &str,
+) -> Result<&'a [u8]> {
+    let sec = elf.section_headers.iter().find(|sh| {
+        if let Some(Ok(n)) = elf.shdr_strtab.get(sh.sh_name) {
+            n == name
+        } else {
+            false
         }
-        _ => None,
+    });
+
+    if let Some(sec) = sec {
+        let offset = sec.sh_offset as usize;
+        let size = sec.sh_size as usize;
+        buffer
+            .get(offset..offset + size)
+            .ok_or_else(|| anyhow!("bad offset/size for ELF section {}", name))
+    } else {
+        Ok(&[])
     }
 }
 
+///
+/// If the specified unit is the skeleton of a unit compiled with split
+/// DWARF, returns the name of the split debug object (.dwo file) that
+/// contains its contents.
+///
+fn dwarf_dwo_name<R: gimli::Reader<Offset = usize>>(
+    dwarf: &gimli::Dwarf<R>,
+    unit: &gimli::Unit<R>,
+) -> Result<Option<String>> {
+    let mut entries = unit.entries();
+
+    let Some((_, root)) = entries.next_dfs()? else {
+        return Ok(None);
+    };
+
+    for attr in
+        [gimli::constants::DW_AT_dwo_name, gimli::constants::DW_AT_GNU_dwo_name]
+    {
+        if let Some(value) = root.attr_value(attr)? {
+            let name = dwarf.attr_string(unit, value)?;
+            return Ok(Some(name.to_string_lossy()?.into_owned()));
+        }
+    }
+
+    Ok(None)
+}
+
+///
+/// Returns the DWO ID in the header of each unit in a .debug_info section, in
+/// the order of the units.  DWARF 5 places the DWO ID of a skeleton unit or a
+/// split unit in its header; other units have none.  (Units compiled with the
+/// GNU extension to DWARF 4 instead have a `DW_AT_GNU_dwo_id` attribute.)
+///
+fn dwarf_header_dwo_ids(section: &[u8]) -> Vec<Option<u64>> {
+    const DW_UT_SKELETON: u8 = 0x04;
+    const DW_UT_SPLIT_COMPILE: u8 = 0x05;
+
+    let u16_at = |o: usize| {
+        section.get(o..o + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+
+    let u32_at = |o: usize| {
+        section.get(o..o + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+    };
+
+    let u64_at = |o: usize| {
+        section.get(o..o + 8).map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+    };
+
+    let mut ids = vec![];
+    let mut offset = 0;
+
+    while let Some(length) = u32_at(offset) {
+        //
+        // A unit in the 64-bit DWARF format has an escaped length, and
+        // 8-byte section offsets.
+        //
+        let (length, start, word) = if length == 0xffff_ffff {
+            match u64_at(offset + 4) {
+                Some(length) => (length as usize, offset + 12, 8),
+                None => break,
+            }
+        } else {
+            (length as usize, offset + 4, 4)
+        };
+
+        //
+        // In DWARF 5, the version is followed by the unit type and the
+        // address size, the offset of the abbreviations -- and then, for
+        // skeleton and split units, the DWO ID.
+        //
+        let id = match (u16_at(start), section.get(start + 2)) {
+            (Some(5), Some(&DW_UT_SKELETON | &DW_UT_SPLIT_COMPILE)) => {
+                u64_at(start + 4 + word)
+            }
+            _ => None,
+        };
+
+        ids.push(id);
+        offset = start.saturating_add(length);
+    }
+
+    ids
+}
+
+///
+/// Returns the DWO ID of a skeleton unit or a split unit, given the DWO ID
+/// (if any) found in its header.
+///
+fn dwarf_dwo_id<R: gimli::Reader<Offset = usize>>(
+    unit: &gimli::Unit<R>,
+    header: Option<u64>,
+) -> Result<Option<u64>> {
+    if header.is_some() {
+        return Ok(header);
+    }
+
+    let mut entries = unit.entries();
+
+    let Some((_, root)) = entries.next_dfs()? else {
+        return Ok(None);
+    };
+
+    Ok(root
+        .attr_value(gimli::constants::DW_AT_GNU_dwo_id)?
+        .and_then(|value| value.udata_value()))
+}
+
+///
+/// Prepares a unit from a split debug object to be read, as newer versions
+/// of gimli do with `Dwarf::make_dwo`:  its address table (and, in DWARF 4,
+/// its range lists) are those of its skeleton, and -- because a split unit
+/// cannot specify the bases of its own tables -- in DWARF 5, its string
+/// offsets, location lists and range lists start just past the headers of
+/// their respective sections.
+///
+fn dwarf_split_unit<R: gimli::Reader<Offset = usize>>(
+    unit: &mut gimli::Unit<R>,
+    skeleton: &gimli::Unit<R>,
+) {
+    unit.addr_base = skeleton.addr_base;
+
+    let encoding = unit.encoding();
+
+    if encoding.version < 5 {
+        unit.rnglists_base = skeleton.rnglists_base;
+        return;
+    }
+
+    //
+    // The header of the string offsets table consists of the initial
+    // length, a 2-byte version and 2 bytes of padding; those of location
+    // and range lists consist of the initial length, a 2-byte version, the
+    // address and segment selector sizes, and a 4-byte offset entry count.
+    //
+    let header = encoding.format.initial_length_size() as usize;
+
+    unit.str_offsets_base = gimli::DebugStrOffsetsBase(header + 4);
+    unit.loclists_base = gimli::DebugLocListsBase(header + 8);
+    unit.rnglists_base = gimli::DebugRngListsBase(header + 8);
+}
+
 /// Demangles `name` as a Rust symbol.
 fn demangle_name(name: &str) -> String {
     // Note: "alternate mode" # causes rustc_demangle to leave off the ugly hash
     // values on functions.
     format!("{:#}", rustc_demangle::demangle(name))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn region(base: u32, size: u32) -> HubrisRegion {
+        HubrisRegion {
+            daddr: None,
+            base,
+            size,
+            attr: HubrisRegionAttr {
+                read: true,
+                write: true,
+                execute: false,
+                device: false,
+                dma: false,
+                external: false,
+            },
+            tasks: vec![HubrisTask::Kernel],
+        }
+    }
+
+    fn regions(r: &[(u32, u32)]) -> BTreeMap<u32, HubrisRegion> {
+        r.iter().map(|&(base, size)| (base, region(base, size))).collect()
+    }
+
+    fn containing(map: &BTreeMap<u32, HubrisRegion>, addr: u32) -> Option<u32> {
+        HubrisArchive::region_containing(map, addr).map(|r| r.base)
+    }
+
+    #[test]
+    fn test_region_containing_boundaries() {
+        let map = regions(&[(0x1000, 0x100)]);
+
+        assert_eq!(containing(&map, 0x0fff), None);
+        assert_eq!(containing(&map, 0x1000), None);
+        assert_eq!(containing(&map, 0x1001), Some(0x1000));
+        assert_eq!(containing(&map, 0x1100), Some(0x1000));
+        assert_eq!(containing(&map, 0x1101), None);
+    }
+
+    #[test]
+    fn test_region_containing_adjacent() {
+        let map = regions(&[(0x1000, 0x100), (0x1100, 0x100)]);
+
+        assert_eq!(containing(&map, 0x1100), Some(0x1000));
+        assert_eq!(containing(&map, 0x1101), Some(0x1100));
+        assert_eq!(containing(&map, 0x1200), Some(0x1100));
+        assert_eq!(containing(&map, 0x1201), None);
+    }
+
+    #[test]
+    fn test_region_containing_overlapping() {
+        //
+        // This is a deliberate change from the linear scan that preceded
+        // region_containing (and differs from region_at):  only the region
+        // with the greatest base below the address is considered, so 0x1300
+        // is not found, even though it lies within the region at 0x1000.
+        //
+        let map = regions(&[(0x1000, 0x400), (0x1100, 0x100)]);
+
+        assert_eq!(containing(&map, 0x1080), Some(0x1000));
+        assert_eq!(containing(&map, 0x1180), Some(0x1100));
+        assert_eq!(containing(&map, 0x1300), None);
+    }
+
+    #[test]
+    fn test_region_containing_end_of_address_space() {
+        let map = regions(&[(0xffff_ff00, 0x100)]);
+
+        assert_eq!(containing(&map, 0xffff_ff00), None);
+        assert_eq!(containing(&map, 0xffff_ffff), Some(0xffff_ff00));
+    }
+
+    #[test]
+    fn test_region_at_boundaries() {
+        let map = regions(&[(0x1000, 0x100), (0x1100, 0x100)]);
+        let at = |addr| HubrisArchive::region_at(&map, addr).map(|r| r.base);
+
+        assert_eq!(at(0x0fff), None);
+        assert_eq!(at(0x1000), Some(0x1000));
+        assert_eq!(at(0x10ff), Some(0x1000));
+        assert_eq!(at(0x1100), Some(0x1100));
+        assert_eq!(at(0x11ff), Some(0x1100));
+        assert_eq!(at(0x1200), None);
+    }
+
+    #[test]
+    fn test_region_at_end_of_address_space() {
+        let map = regions(&[(0xffff_ff00, 0x100)]);
+        let at = |addr| HubrisArchive::region_at(&map, addr).map(|r| r.base);
+
+        assert_eq!(at(0xffff_feff), None);
+        assert_eq!(at(0xffff_ffff), Some(0xffff_ff00));
+    }
+
+    #[test]
+    fn test_region_owner() {
+        let mut map = regions(&[(0x1000, 0x100), (0x1100, 0x100)]);
+        map.get_mut(&0x1000).unwrap().tasks = vec![HubrisTask::Task(1)];
+        map.get_mut(&0x1100).unwrap().tasks =
+            vec![HubrisTask::Task(1), HubrisTask::Task(2)];
+
+        let owner = |addr| {
+            HubrisArchive::region_owner(&map, addr).map(|(r, t)| (r.base, t))
+        };
+
+        assert_eq!(owner(0x0fff), None);
+        assert_eq!(owner(0x1000), Some((0x1000, HubrisTask::Task(1))));
+        assert_eq!(owner(0x10ff), Some((0x1000, HubrisTask::Task(1))));
+        assert_eq!(owner(0x1100), None);
+    }
+
+    #[test]
+    fn test_region_kind() {
+        let mut r = region(0x1000, 0x100);
+        assert_eq!(r.kind(), HubrisRegionKind::Ram);
+
+        r.attr.write = false;
+        r.attr.execute = true;
+        assert_eq!(r.kind(), HubrisRegionKind::Flash);
+
+        r.attr.write = true;
+        r.attr.execute = false;
+        r.attr.device = true;
+        assert_eq!(r.kind(), HubrisRegionKind::Device);
+    }
+
+    #[test]
+    fn test_lookup_struct_byname_cached() {
+        let mut hubris = HubrisArchive::new().unwrap();
+
+        //
+        // Two identical definitions of the same structure (e.g., from
+        // different compilation units) should resolve to just one of them.
+        //
+        for goff in [1, 2] {
+            let goff = HubrisGoff { object: 0, goff };
+
+            hubris.structs.insert(
+                goff,
+                HubrisStruct {
+                    name: "Task".to_string(),
+                    goff,
+                    size: 8,
+                    members: vec![],
+                    namespace: None,
+                },
+            );
+
+            hubris.structs_byname.insert("Task".to_string(), goff);
+        }
+
+        let first = hubris.lookup_struct_byname("Task").unwrap().goff;
+        assert_eq!(first, HubrisGoff { object: 0, goff: 2 });
+
+        let resolved =
+            hubris.structs_resolved.lock().unwrap().get("Task").copied();
+        assert_eq!(resolved, Some(first));
+
+        let again = hubris.lookup_struct_byname("Task").unwrap().goff;
+        assert_eq!(again, first);
+
+        assert!(hubris.lookup_struct_byname("Nonexistent").is_err());
+        assert!(hubris
+            .structs_resolved
+            .lock()
+            .unwrap()
+            .get("Nonexistent")
+            .is_none());
+    }
+
+    #[test]
+    fn test_read_u32_endianness() {
+        let mut hubris = HubrisArchive::new().unwrap();
+        let buf = [0x12, 0x34, 0x56, 0x78, 0x9a];
+
+        assert_eq!(hubris.endianness(), HubrisEndianness::Little);
+        assert_eq!(hubris.read_u32(&buf).unwrap(), 0x7856_3412);
+
+        hubris.endianness = Some(HubrisEndianness::Big);
+        assert_eq!(hubris.read_u32(&buf).unwrap(), 0x1234_5678);
+        assert_eq!(hubris.read_u32(&buf[1..]).unwrap(), 0x3456_789a);
+
+        assert!(hubris.read_u32(&buf[2..]).is_err());
+    }
+
+    #[test]
+    fn test_unmapped_in_task() {
+        let mut map = regions(&[(0x1000, 0x100), (0x2000, 0x100)]);
+        let task = HubrisTask::Task(1);
+
+        for base in [0x1100, 0x2000] {
+            let mut r = region(base, 0x100);
+            r.tasks = vec![task];
+            map.insert(base, r);
+        }
+
+        let unmapped =
+            |addr, len| HubrisArchive::unmapped_in_task(&map, task, addr, len);
+
+        assert_eq!(unmapped(0x1100, 0x100), None);
+        assert_eq!(unmapped(0x10f0, 4), Some(0x10f0));
+        assert_eq!(unmapped(0x11f0, 0x20), Some(0x1200));
+        assert_eq!(unmapped(0x2000, 0x100), None);
+        assert_eq!(unmapped(0x3000, 0), None);
+
+        assert_eq!(
+            HubrisArchive::unmapped_in_task(
+                &map,
+                HubrisTask::Kernel,
+                0x1000,
+                0x200
+            ),
+            Some(0x1100)
+        );
+    }
+
+    #[test]
+    fn test_short_type_name() {
+        assert_eq!(short_type_name("u32"), "u32");
+        assert_eq!(short_type_name("kern::task::Task"), "Task");
+        assert_eq!(short_type_name("*const kern::task::Task"), "*const Task");
+        assert_eq!(
+            short_type_name("Option<core::ptr::NonNull<kern::task::Task>>"),
+            "Option<NonNull<Task>>"
+        );
+        assert_eq!(
+            short_type_name("(a::B, [c::D; 4], &mut e::F)"),
+            "(B, [D; 4], &mut F)"
+        );
+    }
+
+    #[test]
+    fn test_decode_pod() {
+        let mut hubris = HubrisArchive::new().unwrap();
+        let buf = [0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0];
+
+        assert_eq!(hubris.decode_pod::<u8>(&buf).unwrap(), 0x12);
+        assert_eq!(hubris.decode_pod::<u16>(&buf).unwrap(), 0x3412);
+        assert_eq!(hubris.decode_pod::<i16>(&buf[6..]).unwrap(), -0x0f22);
+        assert_eq!(
+            hubris.decode_pod::<u64>(&buf).unwrap(),
+            0xf0de_bc9a_7856_3412
+        );
+
+        hubris.endianness = Some(HubrisEndianness::Big);
+        assert_eq!(hubris.decode_pod::<u16>(&buf).unwrap(), 0x1234);
+        assert_eq!(
+            hubris.decode_pod::<u64>(&buf).unwrap(),
+            0x1234_5678_9abc_def0
+        );
+
+        assert!(hubris.decode_pod::<u64>(&buf[1..]).is_err());
+    }
+
+    #[test]
+    fn test_region_containing_gap() {
+        let map = regions(&[(0x1000, 0x100), (0x2000, 0x100)]);
+
+        assert_eq!(containing(&map, 0x1800), None);
+        assert_eq!(containing(&map, 0x2080), Some(0x2000));
+    }
+
+    #[test]
+    fn test_load_bitfield() {
+        use crate::reflect::{load_bitfield, Base};
+
+        let u8 = HubrisBasetype { encoding: HubrisEncoding::Unsigned, size: 1 };
+        let i8 = HubrisBasetype { encoding: HubrisEncoding::Signed, size: 1 };
+        let u32 =
+            HubrisBasetype { encoding: HubrisEncoding::Unsigned, size: 4 };
+        let bf = |offset, size| HubrisBitfield { offset, size };
+
+        // 0b1011_0110, 0b0000_0011
+        let buf = [0xb6, 0x03];
+
+        let v = load_bitfield(&buf, &u8, 0, bf(1, 2)).unwrap();
+        assert!(matches!(v, Base::U8(0b11)));
+
+        let v = load_bitfield(&buf, &u8, 0, bf(4, 4)).unwrap();
+        assert!(matches!(v, Base::U8(0b1011)));
+
+        let v = load_bitfield(&buf, &i8, 0, bf(4, 4)).unwrap();
+        assert!(matches!(v, Base::I8(-5)));
+
+        let v = load_bitfield(&buf, &u32, 0, bf(7, 3)).unwrap();
+        assert!(matches!(v, Base::U32(0b111)));
+
+        let v = load_bitfield(&buf, &u8, 1, bf(0, 1)).unwrap();
+        assert!(matches!(v, Base::U8(1)));
+
+        assert!(load_bitfield(&buf, &u8, 1, bf(4, 8)).is_err());
+        assert!(load_bitfield(&buf, &u8, 0, bf(0, 0)).is_err());
+    }
+
+    fn split_dwarf5_fixture(name: &str) -> Vec<u8> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/split-dwarf5");
+
+        fs::read(dir.join(name)).unwrap()
+    }
+
+    ///
+    /// Loads the DWARF of the fixture built from split.c with:
+    ///
+    ///   gcc -g -gdwarf-5 -gsplit-dwarf -fdebug-prefix-map=$PWD=. \
+    ///       -fno-asynchronous-unwind-tables -c split.c -o split.o
+    ///   gcc -shared -nostdlib -o split.elf split.o
+    ///
+    /// The contents of its split unit (including the name of its structure,
+    /// which is referred to through the string offsets table) are in
+    /// split.dwo, which we present as though it were in the archive.
+    ///
+    fn split_dwarf5(dwo: Vec<u8>) -> HubrisObjectLoader {
+        let buffer = split_dwarf5_fixture("split.elf");
+        let elf = Elf::parse(&buffer).unwrap();
+
+        let mut loader = HubrisObjectLoader::new(0).unwrap();
+        loader.dwos = Arc::new(HashMap::from([("split.dwo".to_string(), dwo)]));
+        loader.load_object_dwarf(&buffer, &elf).unwrap();
+        loader
+    }
+
+    #[test]
+    fn test_split_dwarf5() {
+        let loader = split_dwarf5(split_dwarf5_fixture("split.dwo"));
+
+        let goff = *loader.structs_byname.get("split_point").unwrap();
+        let s = &loader.structs[&goff];
+        assert_eq!(s.size, 8);
+
+        let members = s
+            .members
+            .iter()
+            .map(|m| (m.name.as_str(), m.offset))
+            .collect::<Vec<_>>();
+        assert_eq!(members, vec![("x", 0), ("y", 4)]);
+    }
+
+    #[test]
+    fn test_split_dwarf5_stale() {
+        let mut dwo = split_dwarf5_fixture("split.dwo");
+
+        //
+        // Changing the DWO ID in the header of the split unit makes the
+        // object stale with respect to its skeleton; it should be ignored.
+        //
+        let (offset, size) = {
+            let elf = Elf::parse(&dwo).unwrap();
+            let info = elf
+                .section_headers
+                .iter()
+                .find(|sh| {
+                    matches!(
+                        elf.shdr_strtab.get(sh.sh_name),
+                        Some(Ok(".debug_info.dwo"))
+                    )
+                })
+                .unwrap();
+
+            (info.sh_offset as usize, info.sh_size as usize)
+        };
+
+        let ids = dwarf_header_dwo_ids(&dwo[offset..offset + size]);
+        assert_eq!(ids.len(), 1);
+        assert!(ids[0].is_some());
+
+        //
+        // The DWO ID follows the 4-byte length, 2-byte version, unit type,
+        // address size and 4-byte abbreviations offset.
+        //
+        dwo[offset + 12] ^= 0xff;
+
+        let loader = split_dwarf5(dwo);
+        assert!(loader.structs_byname.get("split_point").is_none());
+        assert!(loader.structs.is_empty());
+    }
+
+    #[test]
+    fn test_line_writer() {
+        use std::io::Write;
+
+        let lines = |writes: &[&str]| {
+            let mut lines = vec![];
+            let mut out =
+                HubrisLineWriter::new(|l: &str| lines.push(l.to_string()));
+
+            for w in writes {
+                out.write_all(w.as_bytes()).unwrap();
+            }
+
+            out.finish();
+            lines
+        };
+
+        assert_eq!(lines(&[]), vec![""]);
+        assert_eq!(
+            lines(&["a {\n", "    b", ": 1\n}"]),
+            vec!["a {", "    b: 1", "}"]
+        );
+        assert_eq!(lines(&["a\n\nb\n"]), vec!["a", "", "b", ""]);
+    }
+
+    #[test]
+    fn test_follow_vecs() {
+        let mut hubris = HubrisArchive::new().unwrap();
+        let goff = |goff| HubrisGoff { object: 0, goff };
+
+        let base = |encoding, size| HubrisBasetype { encoding, size };
+        hubris.basetypes.insert(goff(1), base(HubrisEncoding::Unsigned, 1));
+        hubris.basetypes.insert(goff(2), base(HubrisEncoding::Unsigned, 4));
+        hubris.arrays.insert(goff(3), HubrisArray { goff: goff(1), count: 4 });
+
+        let member = |offset, name: &str, goff| HubrisStructMember {
+            offset,
+            name: name.to_string(),
+            goff,
+            bitfield: None,
+        };
+
+        hubris.structs.insert(
+            goff(4),
+            HubrisStruct {
+                name: "Vec<u8, 4>".to_string(),
+                goff: goff(4),
+                size: 8,
+                members: vec![
+                    member(0, "buffer", goff(3)),
+                    member(4, "len", goff(2)),
+                ],
+                namespace: None,
+            },
+        );
+
+        let live = |buf: &[u8]| {
+            let mut value = hubris.read_value(buf, goff(4)).unwrap();
+            value.follow_vecs();
+
+            let s = value.as_struct().unwrap();
+            s["buffer"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|v| v.as_base().unwrap().as_u8().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(live(&[1, 2, 3, 4, 2, 0, 0, 0]), vec![1, 2]);
+        assert!(live(&[1, 2, 3, 4, 0, 0, 0, 0]).is_empty());
+
+        //
+        // A length that exceeds the capacity isn't a vector that we
+        // understand; it should be left alone.
+        //
+        assert_eq!(live(&[1, 2, 3, 4, 9, 0, 0, 0]), vec![1, 2, 3, 4]);
+    }
+}
@@ -83,8 +83,9 @@ use anyhow::{anyhow, bail, Context, Result};
 
 use crate::core::Core;
 use crate::hubris::{
-    HubrisArchive, HubrisArray, HubrisBasetype, HubrisEnum, HubrisGoff,
-    HubrisPrintFormat, HubrisStruct, HubrisType, HubrisUnion,
+    HubrisArchive, HubrisArray, HubrisBasetype, HubrisBitfield, HubrisEncoding,
+    HubrisEnum, HubrisGoff, HubrisPrintFormat, HubrisStruct,
+    HubrisStructMember, HubrisType, HubrisUnion,
 };
 
 // Re-export so that others can use #[derive(Load)]
@@ -101,6 +102,14 @@ pub trait Format {
     ) -> Result<()>;
 }
 
+/// Trait for converting reflected values into JSON.  Structs become objects,
+/// arrays and tuples become arrays, and enums are externally tagged (that is,
+/// a variant with contents becomes an object with a single member named for
+/// the variant; a variant without contents becomes a string).
+pub trait Json {
+    fn to_json(&self, hubris: &HubrisArchive) -> Result<serde_json::Value>;
+}
+
 /// A dynamic representation of some data extracted from a program image.
 ///
 /// Each variant of `Value` captures a different class of types, and thus
@@ -203,6 +212,61 @@ impl Value {
         }
         Ok(())
     }
+
+    /// Returns true if this is a zero (or otherwise default) value:  a zero
+    /// or false basetype, a null pointer, `None`, or an aggregate whose
+    /// members are all zero.
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Value::Base(b) => b.is_zero(),
+            Value::Ptr(p) => p.addr() == 0,
+            Value::Enum(e) => e.disc() == "None" && e.contents().is_none(),
+            Value::Struct(s) => s.iter().all(|(_, v)| v.is_zero()),
+            Value::Tuple(t) => t.iter().all(Value::is_zero),
+            Value::Array(a) => a.iter().all(Value::is_zero),
+        }
+    }
+
+    /// Truncates any fixed-capacity vectors within this value (as with
+    /// `heapless::Vec`) to their live elements.  A fixed-capacity vector is
+    /// recognized by its structure:  a struct with an unsigned `len` member
+    /// and a `buffer` (or `data`) array of at least `len` elements.
+    pub fn follow_vecs(&mut self) {
+        match self {
+            Value::Struct(s) => {
+                let len = match s.get("len") {
+                    Some(Value::Base(Base::U8(len))) => Some(*len as usize),
+                    Some(Value::Base(Base::U16(len))) => Some(*len as usize),
+                    Some(Value::Base(Base::U32(len))) => Some(*len as usize),
+                    Some(Value::Base(Base::U64(len))) => Some(*len as usize),
+                    _ => None,
+                };
+
+                let buffer = ["buffer", "data"]
+                    .into_iter()
+                    .find(|&name| matches!(s.get(name), Some(Value::Array(_))))
+                    .and_then(|name| s.members.get_mut(name));
+
+                if let (Some(len), Some(buffer)) = (len, buffer) {
+                    if let Value::Array(a) = &mut **buffer {
+                        if len <= a.0.len() {
+                            a.0.truncate(len);
+                        }
+                    }
+                }
+
+                s.members.values_mut().for_each(|v| v.follow_vecs());
+            }
+            Value::Enum(e) => {
+                if let Some(contents) = &mut e.1 {
+                    contents.follow_vecs();
+                }
+            }
+            Value::Tuple(t) => t.1.iter_mut().for_each(Value::follow_vecs),
+            Value::Array(a) => a.0.iter_mut().for_each(Value::follow_vecs),
+            Value::Base(_) | Value::Ptr(_) => {}
+        }
+    }
 }
 
 impl Format for Value {
@@ -223,6 +287,19 @@ impl Format for Value {
     }
 }
 
+impl Json for Value {
+    fn to_json(&self, hubris: &HubrisArchive) -> Result<serde_json::Value> {
+        match self {
+            Self::Struct(s) => s.to_json(hubris),
+            Self::Enum(s) => s.to_json(hubris),
+            Self::Base(s) => s.to_json(hubris),
+            Self::Tuple(s) => s.to_json(hubris),
+            Self::Array(s) => s.to_json(hubris),
+            Self::Ptr(s) => s.to_json(hubris),
+        }
+    }
+}
+
 /// A value of an enumeration.
 #[derive(Clone, Debug, Default)]
 pub struct Enum(String, Option<Box<Value>>);
@@ -277,6 +354,15 @@ impl Format for Enum {
     }
 }
 
+impl Json for Enum {
+    fn to_json(&self, hubris: &HubrisArchive) -> Result<serde_json::Value> {
+        Ok(match self.contents() {
+            Some(c) => serde_json::json!({ self.disc(): c.to_json(hubris)? }),
+            None => serde_json::Value::from(self.disc()),
+        })
+    }
+}
+
 /// A value of a basetype, often called a "primitive."
 ///
 /// There is one variant of this enum for every fundamental type in Rust, unless
@@ -303,6 +389,26 @@ pub enum Base {
 }
 
 impl Base {
+    /// Returns true if this is zero (or false).
+    pub fn is_zero(&self) -> bool {
+        match *self {
+            Self::I8(x) => x == 0,
+            Self::I16(x) => x == 0,
+            Self::I32(x) => x == 0,
+            Self::I64(x) => x == 0,
+            Self::I128(x) => x == 0,
+            Self::U0 => true,
+            Self::U8(x) => x == 0,
+            Self::U16(x) => x == 0,
+            Self::U32(x) => x == 0,
+            Self::U64(x) => x == 0,
+            Self::U128(x) => x == 0,
+            Self::Bool(x) => !x,
+            Self::F32(x) => x == 0.0,
+            Self::F64(x) => x == 0.0,
+        }
+    }
+
     /// "Downcasts" this to a `bool`, returning `None` if it isn't one.
     pub fn as_bool(&self) -> Option<bool> {
         if let Self::Bool(x) = *self {
@@ -357,6 +463,26 @@ impl Base {
         }
     }
 
+    /// Converts this to an `f64`, returning `None` if it isn't numeric.  (Large
+    /// 64- and 128-bit integers will lose precision.)
+    pub fn to_f64(&self) -> Option<f64> {
+        match *self {
+            Self::I8(x) => Some(x as f64),
+            Self::I16(x) => Some(x as f64),
+            Self::I32(x) => Some(x as f64),
+            Self::I64(x) => Some(x as f64),
+            Self::I128(x) => Some(x as f64),
+            Self::U8(x) => Some(x as f64),
+            Self::U16(x) => Some(x as f64),
+            Self::U32(x) => Some(x as f64),
+            Self::U64(x) => Some(x as f64),
+            Self::U128(x) => Some(x as f64),
+            Self::F32(x) => Some(x as f64),
+            Self::F64(x) => Some(x),
+            Self::Bool(_) | Self::U0 => None,
+        }
+    }
+
     /// Checks whether this is a type that should be printed in hex (with a
     /// leading '0x' prefix), returing true if that's reasonable.
     ///
@@ -461,8 +587,25 @@ impl Format for Base {
         fmt: HubrisPrintFormat,
         out: &mut dyn std::io::Write,
     ) -> Result<()> {
+        //
+        // If we have been asked to scale numeric values, we display them
+        // (in decimal) multiplied by the scale, rounded to six places and
+        // followed by the unit, if any.
+        //
+        if let (Some(scale), Some(x)) = (fmt.scale, self.to_f64()) {
+            write!(out, "{}", (x * scale * 1e6).round() / 1e6)?;
+
+            if let Some(unit) = fmt.unit {
+                write!(out, " {}", unit)?;
+            }
+
+            return Ok(());
+        }
+
         // Special case for booleans, because "0xfalse" looks silly
-        if fmt.hex && self.supports_hex() {
+        if fmt.binary && self.supports_hex() {
+            write!(out, "0b{:b}", self)?;
+        } else if fmt.hex && self.supports_hex() {
             write!(out, "0x{:x}", self)?;
         } else {
             write!(out, "{}", self)?;
@@ -471,6 +614,36 @@ impl Format for Base {
     }
 }
 
+impl Json for Base {
+    fn to_json(&self, _hubris: &HubrisArchive) -> Result<serde_json::Value> {
+        use serde_json::Value as J;
+
+        //
+        // JSON numbers can't represent 128-bit values, so we represent those
+        // as strings.
+        //
+        Ok(match *self {
+            Self::U0 => J::Null,
+            Self::U8(x) => J::from(x),
+            Self::U16(x) => J::from(x),
+            Self::U32(x) => J::from(x),
+            Self::U64(x) => J::from(x),
+            Self::U128(x) => J::from(x.to_string()),
+
+            Self::I8(x) => J::from(x),
+            Self::I16(x) => J::from(x),
+            Self::I32(x) => J::from(x),
+            Self::I64(x) => J::from(x),
+            Self::I128(x) => J::from(x.to_string()),
+
+            Self::F32(x) => J::from(x),
+            Self::F64(x) => J::from(x),
+
+            Self::Bool(x) => J::from(x),
+        })
+    }
+}
+
 /// A struct with named fields.
 #[derive(Clone, Debug, Default)]
 pub struct Struct {
@@ -536,13 +709,24 @@ impl Format for Struct {
             return Ok(());
         }
 
-        fmt.indent += 4;
+        fmt.indent += fmt.indent_width();
 
         let print_name = if fmt.no_name { "" } else { self.name() };
+        let print_name = fmt.type_name(print_name);
         write!(out, "{} {{{}", print_name, fmt.delim())?;
         fmt.no_name = false;
 
-        for (i, (name, value)) in self.iter().enumerate() {
+        //
+        // If we have been asked to skip default fields, we elide any member
+        // that is zero, and indicate how many we left out.
+        //
+        let members = self
+            .iter()
+            .filter(|(_, v)| !fmt.skip_default || !v.is_zero())
+            .collect::<Vec<_>>();
+        let omitted = self.len() - members.len();
+
+        for (i, (name, value)) in members.iter().enumerate() {
             if fmt.newline && fmt.indent > 0 {
                 write!(out, "{:1$}", " ", fmt.indent)?;
             }
@@ -551,14 +735,27 @@ impl Format for Struct {
 
             value.format(hubris, fmt, out)?;
 
-            if i + 1 < self.len() {
+            if i + 1 < members.len() || omitted > 0 {
                 write!(out, ",{}", fmt.delim())?;
             }
         }
 
+        if omitted > 0 {
+            if fmt.newline && fmt.indent > 0 {
+                write!(out, "{:1$}", " ", fmt.indent)?;
+            }
+
+            write!(
+                out,
+                "... ({} field{} omitted)",
+                omitted,
+                if omitted == 1 { "" } else { "s" }
+            )?;
+        }
+
         write!(out, "{}", fmt.delim())?;
 
-        fmt.indent -= 4;
+        fmt.indent -= fmt.indent_width();
 
         if fmt.newline && fmt.indent > 0 {
             write!(out, "{:1$}", " ", fmt.indent)?;
@@ -569,6 +766,18 @@ impl Format for Struct {
     }
 }
 
+impl Json for Struct {
+    fn to_json(&self, hubris: &HubrisArchive) -> Result<serde_json::Value> {
+        let mut map = serde_json::Map::new();
+
+        for (name, value) in self.iter() {
+            map.insert(name.to_string(), value.to_json(hubris)?);
+        }
+
+        Ok(serde_json::Value::Object(map))
+    }
+}
+
 /// A tuple or tuple struct.
 #[derive(Clone, Debug, Default)]
 pub struct Tuple(String, Vec<Value>);
@@ -595,7 +804,7 @@ impl Format for Tuple {
         mut fmt: HubrisPrintFormat,
         out: &mut dyn std::io::Write,
     ) -> Result<()> {
-        fmt.indent += 4;
+        fmt.indent += fmt.indent_width();
 
         // Is this a bitflags-generated type? If so, just format it as a binary
         // value.
@@ -611,7 +820,7 @@ impl Format for Tuple {
         if !fmt.no_name
             && (!self.name().starts_with('(') || !self.name().ends_with(')'))
         {
-            write!(out, "{}", self.name())?;
+            write!(out, "{}", fmt.type_name(self.name()))?;
         }
         fmt.no_name = false;
 
@@ -637,6 +846,22 @@ impl Format for Tuple {
     }
 }
 
+impl Json for Tuple {
+    fn to_json(&self, hubris: &HubrisArchive) -> Result<serde_json::Value> {
+        //
+        // As with formatting, a newtype is represented as its contents.
+        //
+        if self.len() == 1 && !self.name().starts_with('(') {
+            return self[0].to_json(hubris);
+        }
+
+        let elements =
+            self.iter().map(|v| v.to_json(hubris)).collect::<Result<_>>()?;
+
+        Ok(serde_json::Value::Array(elements))
+    }
+}
+
 /// An array, e.g. `[T; N]`.
 #[derive(Clone, Debug, Default)]
 pub struct Array(Vec<Value>);
@@ -665,6 +890,27 @@ impl Array {
         write!(out, "{:?}", String::from_utf8_lossy(&bytes))?;
         Ok(())
     }
+
+    /// If this is an array of `u8` whose contents up to the first NUL are
+    /// non-empty and printable ASCII, returns those contents as a string.
+    pub fn as_text(&self) -> Option<String> {
+        let mut text = String::new();
+        for v in &self.0 {
+            let b = v.as_base().ok().and_then(Base::as_u8)?;
+            if b == 0 {
+                break;
+            }
+            if !b.is_ascii_graphic() && b != b' ' {
+                return None;
+            }
+            text.push(b as char);
+        }
+        if text.is_empty() {
+            None
+        } else {
+            Some(text)
+        }
+    }
 }
 
 impl Format for Array {
@@ -677,7 +923,13 @@ impl Format for Array {
         if fmt.interpret_as_c_string {
             return self.format_as_c_string(out);
         }
-        fmt.indent += 4;
+        if fmt.interpret_as_text {
+            if let Some(text) = self.as_text() {
+                write!(out, "{:?}", text)?;
+                return Ok(());
+            }
+        }
+        fmt.indent += fmt.indent_width();
         fmt.no_name = false;
 
         write!(out, "[{}", fmt.delim())?;
@@ -696,7 +948,7 @@ impl Format for Array {
 
         write!(out, "{}", fmt.delim())?;
 
-        fmt.indent -= 4;
+        fmt.indent -= fmt.indent_width();
 
         if fmt.newline && fmt.indent > 0 {
             write!(out, "{:1$}", " ", fmt.indent)?;
@@ -708,6 +960,15 @@ impl Format for Array {
     }
 }
 
+impl Json for Array {
+    fn to_json(&self, hubris: &HubrisArchive) -> Result<serde_json::Value> {
+        let elements =
+            self.iter().map(|v| v.to_json(hubris)).collect::<Result<_>>()?;
+
+        Ok(serde_json::Value::Array(elements))
+    }
+}
+
 /// A pointer with an embedded type.
 ///
 /// The type is of the _pointer_, not the pointed-to item, so that we can
@@ -748,15 +1009,104 @@ impl Format for Ptr {
     fn format(
         &self,
         hubris: &HubrisArchive,
-        _fmt: HubrisPrintFormat,
+        fmt: HubrisPrintFormat,
         out: &mut dyn std::io::Write,
     ) -> Result<()> {
         let name = hubris.lookup_type(self.ptr_goff())?.name(hubris)?;
-        write!(out, "0x{:x} ({})", self.addr(), name)?;
+        write!(out, "0x{:x} ({})", self.addr(), fmt.type_name(&name))?;
         Ok(())
     }
 }
 
+impl Json for Ptr {
+    fn to_json(&self, hubris: &HubrisArchive) -> Result<serde_json::Value> {
+        let name = hubris.lookup_type(self.ptr_goff())?.name(hubris)?;
+
+        Ok(serde_json::json!({ "addr": self.addr(), "type": name }))
+    }
+}
+
+/// Formats a value compactly, as a space-separated list of `path=value`
+/// pairs, one for each scalar within the value (e.g., `state=Running
+/// state.since=0x2a`).  Paths are relative to the value, so a value that is
+/// itself a scalar is formatted as just that scalar.  An enum yields a pair
+/// for its variant, followed by the pairs of the variant's contents, if any.
+pub fn format_compact(
+    value: &Value,
+    hubris: &HubrisArchive,
+    fmt: HubrisPrintFormat,
+    out: &mut dyn std::io::Write,
+) -> Result<()> {
+    fn pairs(
+        value: &Value,
+        hubris: &HubrisArchive,
+        fmt: HubrisPrintFormat,
+        path: &str,
+        out: &mut Vec<String>,
+    ) -> Result<()> {
+        let member = |name: &str| match path {
+            "" => name.to_string(),
+            _ => format!("{path}.{name}"),
+        };
+
+        let leaf = |value: &dyn Format, out: &mut Vec<String>| -> Result<()> {
+            let mut buf = vec![];
+            value.format(hubris, fmt, &mut buf)?;
+            let value = String::from_utf8_lossy(&buf);
+
+            out.push(match path {
+                "" => value.to_string(),
+                _ => format!("{path}={value}"),
+            });
+
+            Ok(())
+        };
+
+        match value {
+            Value::Struct(s) => {
+                for (name, v) in s.iter() {
+                    if !fmt.skip_default || !v.is_zero() {
+                        pairs(v, hubris, fmt, &member(name), out)?;
+                    }
+                }
+            }
+            Value::Tuple(t) => {
+                for (i, v) in t.iter().enumerate() {
+                    pairs(v, hubris, fmt, &member(&i.to_string()), out)?;
+                }
+            }
+            Value::Array(a)
+                if fmt.interpret_as_c_string
+                    || (fmt.interpret_as_text && a.as_text().is_some()) =>
+            {
+                leaf(a, out)?;
+            }
+            Value::Array(a) => {
+                for (i, v) in a.iter().enumerate() {
+                    pairs(v, hubris, fmt, &format!("{path}[{i}]"), out)?;
+                }
+            }
+            Value::Enum(e) => {
+                leaf(&Enum(e.disc().to_string(), None), out)?;
+
+                if let Some(c) = e.contents() {
+                    pairs(c, hubris, fmt, path, out)?;
+                }
+            }
+            Value::Base(b) => leaf(b, out)?,
+            Value::Ptr(p) => leaf(p, out)?,
+        }
+
+        Ok(())
+    }
+
+    let mut out_pairs = vec![];
+    pairs(value, hubris, fmt, "", &mut out_pairs)?;
+    write!(out, "{}", out_pairs.join(" "))?;
+
+    Ok(())
+}
+
 /// Loads data from memory image `buf` at offset `addr` and maps it onto a Rust
 /// `T`.
 pub fn load<'a, T: Load>(
@@ -847,9 +1197,7 @@ pub fn load_struct_or_tuple(
         // order? No idea! But if they do, this method will keep working.
         let mut contents = vec![Value::Base(Base::U8(0)); ty.members.len()];
         for m in &ty.members {
-            let maddr = addr + m.offset;
-            let mty = hubris.lookup_type(m.goff)?;
-            let v = load_value(hubris, buf, mty, maddr)?;
+            let v = load_member(hubris, buf, m, addr)?;
 
             let index = m.name[2..].parse::<usize>()?;
             contents[index] = v;
@@ -871,17 +1219,75 @@ pub fn load_struct(
     let mut s = Struct { name: ty.name.clone(), ..Default::default() };
 
     for m in &ty.members {
-        let maddr = addr + m.offset;
-        let mty = hubris.lookup_type(m.goff)?;
         s.members.insert(
             m.name.clone(),
-            Box::new(load_value(hubris, buf, mty, maddr)?),
+            Box::new(load_member(hubris, buf, m, addr)?),
         );
     }
 
     Ok(s)
 }
 
+/// Loads member `m` of the struct at offset `addr` in memory image `buf`,
+/// extracting its value if it is a bitfield.
+fn load_member(
+    hubris: &HubrisArchive,
+    buf: &[u8],
+    m: &HubrisStructMember,
+    addr: usize,
+) -> Result<Value> {
+    let maddr = addr + m.offset;
+    let mty = hubris.lookup_type(m.goff)?;
+
+    match (m.bitfield, mty) {
+        (None, _) => load_value(hubris, buf, mty, maddr),
+        (Some(bf), HubrisType::Base(bty)) => {
+            load_bitfield(buf, bty, maddr, bf).map(Value::Base)
+        }
+        (Some(_), _) => {
+            bail!("bitfield member {} has non-base type {}", m.name, mty)
+        }
+    }
+}
+
+/// Loads a bitfield of base type `ty` from memory image `buf` at offset
+/// `addr`, sign-extending it if the type is signed.
+pub fn load_bitfield(
+    buf: &[u8],
+    ty: &HubrisBasetype,
+    addr: usize,
+    bf: HubrisBitfield,
+) -> Result<Base> {
+    let end = bf.offset + bf.size;
+
+    if bf.size == 0 || end > 128 {
+        bail!("bitfield of {} bits at bit {} is invalid", bf.size, bf.offset);
+    }
+
+    let nbytes = (end + 7) / 8;
+    let bytes = buf.get(addr..addr + nbytes).ok_or_else(|| {
+        anyhow!("address {} out of range for bitfield {:?}", addr, bf)
+    })?;
+
+    let mut raw = [0u8; 16];
+    raw[..nbytes].copy_from_slice(bytes);
+
+    //
+    // Shift the field to the top of our word and then back down, which
+    // clears the bits above it (or sign-extends it, as the case may be).
+    //
+    let shift = 128 - bf.size;
+    let val = u128::from_le_bytes(raw) << (128 - end);
+
+    let val = if ty.encoding == HubrisEncoding::Signed {
+        ((val as i128) >> shift) as u128
+    } else {
+        val >> shift
+    };
+
+    load_base(&val.to_le_bytes(), ty, 0)
+}
+
 /// Loads an enum from memory image `buf` at offset `addr`.
 pub fn load_enum(
     hubris: &HubrisArchive,
@@ -0,0 +1,6 @@
+struct split_point {
+    unsigned int x;
+    unsigned int y;
+};
+
+struct split_point SPLIT_ORIGIN = { 1, 2 };
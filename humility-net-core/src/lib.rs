@@ -14,7 +14,7 @@
 
 use anyhow::{anyhow, bail, Context, Result};
 use humility::{
-    core::{Core, NetAgent},
+    core::{Core, HaltInfo, NetAgent, CORE_MAX_READSIZE},
     hubris::{HubrisArchive, HubrisFlashMap, HubrisRegion, HubrisTask},
     msg,
     net::ScopedV6Addr,
@@ -357,6 +357,54 @@ impl Core for NetCore {
         self.read(addr, data)
     }
 
+    fn max_transfer_size(&self) -> usize {
+        CORE_MAX_READSIZE
+    }
+
+    fn read_batch(
+        &mut self,
+        requests: &[(u32, usize)],
+    ) -> Result<Vec<Vec<u8>>> {
+        //
+        // Every read over the network is expensive, so we coalesce any
+        // requests that overlap or abut one another into a single read.
+        //
+        let mut spans = requests.to_vec();
+        spans.sort_unstable();
+
+        let mut chunks: Vec<(u32, Vec<u8>)> = vec![];
+
+        for (addr, len) in spans {
+            match chunks.last_mut() {
+                Some((base, buf)) if *base + buf.len() as u32 >= addr => {
+                    let end = (addr - *base) as usize + len;
+
+                    if end > buf.len() {
+                        buf.resize(end, 0);
+                    }
+                }
+                _ => chunks.push((addr, vec![0; len])),
+            }
+        }
+
+        for (base, buf) in chunks.iter_mut() {
+            self.read(*base, buf)?;
+        }
+
+        Ok(requests
+            .iter()
+            .map(|&(addr, len)| {
+                let (base, buf) = chunks
+                    .iter()
+                    .rev()
+                    .find(|(base, _)| *base <= addr)
+                    .unwrap();
+                let offs = (addr - base) as usize;
+                buf[offs..offs + len].to_vec()
+            })
+            .collect())
+    }
+
     fn read_reg(&mut self, reg: ARMRegister) -> Result<u32> {
         bail!("cannot read register {} over network", reg);
     }
@@ -377,6 +425,10 @@ impl Core for NetCore {
         Ok(())
     }
 
+    fn halt_timeout(&mut self, _dur: Duration) -> Result<HaltInfo> {
+        Ok(HaltInfo::default())
+    }
+
     fn run(&mut self) -> Result<()> {
         Ok(())
     }
@@ -110,6 +110,10 @@ pub fn subcommand(
                 bail!("cannot specify probe for {} command", cmd);
             }
 
+            if context.cli.image.is_some() {
+                bail!("cannot specify an image for {} command", cmd);
+            }
+
             (run)(context)
         }
         CommandKind::Raw { .. } => (run)(context),
@@ -11,6 +11,7 @@ use std::{borrow::Cow, collections::HashMap};
 use anyhow::Result;
 use clap::Command as ClapCommand;
 use clap::CommandFactory;
+use humility::hubris::{HubrisArchive, HubrisGoff};
 use humility_cli::{Cli, ExecutionContext};
 use humility_cmd::CommandKind;
 use humility_cmd::{Archive, Attach, Command, Validate};
@@ -78,7 +79,27 @@ fn repl(context: &mut ExecutionContext) -> Result<()> {
 
     let (commands, _) = cmd::init(Cli::command());
 
-    let completer = Box::new(ClapCompleter::new(commands));
+    //
+    // Some commands take names from the archive as arguments; we offer
+    // those names as completions for such arguments, along with the paths
+    // of their structure members (e.g., "config.thresholds").
+    //
+    let mut values = HashMap::new();
+    let mut paths = HashMap::new();
+
+    if let Some(hubris) = &context.archive {
+        let mut variables = hubris
+            .qualified_variables()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>();
+
+        variables.sort();
+        variables.dedup();
+        values.insert("readvar", variables);
+        paths.insert("readvar", MemberPaths::new(hubris));
+    }
+
+    let completer = Box::new(ClapCompleter::new(commands, values, paths));
 
     // Use the interactive menu to select options from the completer
     let completion_menu =
@@ -205,13 +226,109 @@ pub fn init() -> Command {
     }
 }
 
+///
+/// The structure members of each variable in the archive, allowing member
+/// paths (e.g., "config.thresholds") to be completed after a dot.  The
+/// completer can't borrow the archive, so we gather the members of every
+/// structure reachable from a variable up front.
+///
+struct MemberPaths {
+    variables: HashMap<String, Vec<HubrisGoff>>,
+    members: HashMap<HubrisGoff, Vec<(String, HubrisGoff)>>,
+}
+
+impl MemberPaths {
+    fn new(hubris: &HubrisArchive) -> MemberPaths {
+        let mut variables: HashMap<String, Vec<HubrisGoff>> = HashMap::new();
+        let mut members = HashMap::new();
+
+        for (name, v) in hubris.qualified_variables() {
+            variables.entry(name.to_string()).or_default().push(v.goff);
+        }
+
+        let mut pending =
+            variables.values().flatten().copied().collect::<Vec<_>>();
+
+        while let Some(goff) = pending.pop() {
+            if members.contains_key(&goff) {
+                continue;
+            }
+
+            let m = hubris
+                .members(goff)
+                .iter()
+                .map(|m| (m.name.clone(), m.goff))
+                .collect::<Vec<_>>();
+
+            pending.extend(m.iter().map(|(_, goff)| *goff));
+            members.insert(goff, m);
+        }
+
+        MemberPaths { variables, members }
+    }
+
+    ///
+    /// Returns the member paths that complete the specified word, which must
+    /// consist of a variable name followed by a path of members.
+    ///
+    fn complete(&self, word: &str) -> Vec<String> {
+        let Some((path, partial)) = word.rsplit_once('.') else {
+            return vec![];
+        };
+
+        let mut fields = path.split('.');
+        let variable = fields.next().unwrap();
+
+        let Some(goffs) = self.variables.get(variable) else {
+            return vec![];
+        };
+
+        let mut rval = vec![];
+
+        for &goff in goffs {
+            let mut goff = Some(goff);
+
+            for field in fields.clone() {
+                goff = goff
+                    .and_then(|g| self.members.get(&g))
+                    .and_then(|m| m.iter().find(|(name, _)| name == field))
+                    .map(|(_, g)| *g);
+            }
+
+            let Some(members) = goff.and_then(|g| self.members.get(&g)) else {
+                continue;
+            };
+
+            for (name, _) in members {
+                if name.starts_with(partial) {
+                    rval.push(format!("{path}.{name}"));
+                }
+            }
+        }
+
+        rval.sort();
+        rval.dedup();
+        rval
+    }
+}
+
 struct ClapCompleter {
     commands: HashMap<&'static str, Command>,
+
+    /// Values for each command's positional arguments, by command name
+    values: HashMap<&'static str, Vec<String>>,
+
+    /// Member paths within those values, by command name
+    paths: HashMap<&'static str, MemberPaths>,
 }
 
 impl ClapCompleter {
-    fn new(commands: HashMap<&'static str, Command>) -> ClapCompleter {
-        ClapCompleter { commands }
+    fn new(
+        commands: HashMap<&'static str, Command>,
+        values: HashMap<&'static str, Vec<String>>,
+        paths: HashMap<&'static str, MemberPaths>,
+    ) -> ClapCompleter {
+        ClapCompleter { commands, values, paths }
     }
 }
 
@@ -264,6 +381,50 @@ impl Completer for ClapCompleter {
                         }
                     }
 
+                    //
+                    // A value can be completed either from the beginning of
+                    // its qualified name or from the beginning of its last
+                    // component (e.g., "TICKS" in "kern::arch::TICKS").
+                    //
+                    let values = match self.values.get(command.name) {
+                        Some(values) if !last_word.starts_with('-') => {
+                            &values[..]
+                        }
+                        _ => &[],
+                    };
+
+                    for value in values {
+                        let last = value.rsplit("::").next().unwrap();
+
+                        if value.starts_with(last_word)
+                            || last.starts_with(last_word)
+                        {
+                            completions.push(Suggestion {
+                                value: value.clone(),
+                                description: None,
+                                extra: None,
+                                span,
+                                append_whitespace: true,
+                            })
+                        }
+                    }
+
+                    //
+                    // A value followed by a dot is completed with the names
+                    // of the members of its type.
+                    //
+                    if let Some(paths) = self.paths.get(command.name) {
+                        for value in paths.complete(last_word) {
+                            completions.push(Suggestion {
+                                value,
+                                description: None,
+                                extra: None,
+                                span,
+                                append_whitespace: false,
+                            })
+                        }
+                    }
+
                     completions
                 } else {
                     Vec::new()
@@ -44,8 +44,13 @@ fn main() -> Result<()> {
     };
 
     if let Err(err) = cmd::subcommand(&mut context, &commands) {
-        eprintln!("humility {} failed: {:?}", subcmd, err);
-        std::process::exit(1);
+        let exit = err.downcast_ref::<humility_cmd::ExitStatus>();
+
+        if exit.map_or(true, |exit| exit.msg.is_some()) {
+            eprintln!("humility {} failed: {:?}", subcmd, err);
+        }
+
+        std::process::exit(exit.map_or(1, |exit| exit.status.into()));
     }
 
     Ok(())